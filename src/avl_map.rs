@@ -0,0 +1,421 @@
+use std::{borrow::Borrow, marker::PhantomData, mem, ops::RangeBounds, ptr::NonNull};
+
+use crate::avl_tree::{self, AVLNode, Natural, Search, Summary};
+
+/// 基于 AVL 树的有序映射，平衡逻辑与 AVLTree 共用 avl_tree 中的节点实现。
+/// S 为每个节点维护的子树摘要，用于 fold_range 区间聚合。
+///
+/// get_mut 与 entry 只对没有摘要的 `AVLMap<K, V>` 提供：它们交出值的可变引用，
+/// 修改后无法重新计算路径上的摘要。带摘要的映射请用 insert 替换值，或用 update 原地修改
+#[derive(Debug)]
+pub struct AVLMap<K, V, S = ()> {
+  root: Option<Box<AVLNode<K, V, S>>>,
+  length: usize,
+}
+
+impl<K: Ord, V, S: Summary<K, V>> Default for AVLMap<K, V, S> {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl<K: Ord, V, S: Summary<K, V>> AVLMap<K, V, S> {
+  pub fn new() -> Self {
+    Self {
+      root: None,
+      length: 0,
+    }
+  }
+
+  // 插入键值对，键已存在时替换值并返回旧值，原有的键保持不变
+  pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+    let old = avl_tree::insert(&mut self.root, key, value, &Natural).err();
+    if old.is_none() {
+      self.length += 1;
+    }
+    old
+  }
+
+  pub fn get<Q>(&self, key: &Q) -> Option<&V>
+  where
+    K: Borrow<Q>,
+    Q: Ord + ?Sized,
+  {
+    avl_tree::get(&self.root, key, &Natural).map(|node| &node.value)
+  }
+
+  pub fn get_key_value<Q>(&self, key: &Q) -> Option<(&K, &V)>
+  where
+    K: Borrow<Q>,
+    Q: Ord + ?Sized,
+  {
+    avl_tree::get(&self.root, key, &Natural).map(|node| (&node.key, &node.value))
+  }
+
+  // 通过闭包修改键对应的值，修改后重新计算路径上的摘要
+  pub fn update<Q, R>(&mut self, key: &Q, f: impl FnOnce(&mut V) -> R) -> Option<R>
+  where
+    K: Borrow<Q>,
+    Q: Ord + ?Sized,
+  {
+    avl_tree::update(&mut self.root, key, &Natural, f)
+  }
+
+  // 合并键位于 range 内的所有键值对的摘要，O(log n)
+  pub fn fold_range<Q, R>(&self, range: R) -> S
+  where
+    K: Borrow<Q>,
+    Q: Ord + ?Sized,
+    R: RangeBounds<Q>,
+  {
+    avl_tree::fold(&self.root, range.start_bound(), range.end_bound(), &Natural)
+  }
+
+  pub fn contains_key<Q>(&self, key: &Q) -> bool
+  where
+    K: Borrow<Q>,
+    Q: Ord + ?Sized,
+  {
+    avl_tree::get(&self.root, key, &Natural).is_some()
+  }
+
+  pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+  where
+    K: Borrow<Q>,
+    Q: Ord + ?Sized,
+  {
+    self.remove_entry(key).map(|(_, value)| value)
+  }
+
+  pub fn remove_entry<Q>(&mut self, key: &Q) -> Option<(K, V)>
+  where
+    K: Borrow<Q>,
+    Q: Ord + ?Sized,
+  {
+    let removed = avl_tree::remove(&mut self.root, key, &Natural);
+    if removed.is_some() {
+      self.length -= 1;
+    }
+    removed
+  }
+
+  pub fn clear(&mut self) {
+    self.root = None;
+    self.length = 0;
+  }
+
+  pub fn len(&self) -> usize {
+    self.length
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.length == 0
+  }
+
+  pub fn iter(&self) -> Iter<'_, K, V, S> {
+    let mut iter = Iter {
+      node_stack: Vec::with_capacity(self.root.as_ref().map_or(0, |n| n.height)),
+    };
+    iter.push_left_path(&self.root);
+    iter
+  }
+}
+
+// 直接暴露值的可变引用会绕过摘要的重新计算，因此只在没有摘要时提供，
+// 带摘要的映射请使用 insert 或 update
+impl<K: Ord, V> AVLMap<K, V> {
+  /// 返回键对应的值的可变引用，只对没有摘要的映射提供，带摘要时请使用 update
+  pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+  where
+    K: Borrow<Q>,
+    Q: Ord + ?Sized,
+  {
+    avl_tree::get_mut(&mut self.root, key, &Natural).map(|node| &mut node.value)
+  }
+
+  /// 获取键对应的条目，用于原地查找并修改或插入。条目保存这次下降找到的节点，
+  /// 之后读写值不再查找，空条目插入时也只需再下降一次。
+  ///
+  /// 只对没有摘要的映射提供，带摘要时请先用 update 修改已有的值，不存在时再 insert
+  pub fn entry(&mut self, key: K) -> Entry<'_, K, V> {
+    let map = NonNull::from(self);
+    // SAFETY: map 来自 self 的独占借用，之后对映射的所有访问都经由这个指针派生
+    let root = unsafe { &mut (*map.as_ptr()).root };
+    match avl_tree::search_mut(root, &key, &Natural) {
+      Search::Found(node) => Entry::Occupied(OccupiedEntry {
+        key,
+        node: NonNull::from(node),
+        map,
+        marker: PhantomData,
+      }),
+      Search::Vacant(critical) => Entry::Vacant(VacantEntry {
+        key,
+        critical,
+        // SAFETY: 查找时的借用已经结束，重新得到 self 的独占借用
+        map: unsafe { &mut *map.as_ptr() },
+      }),
+    }
+  }
+}
+
+pub enum Entry<'a, K: Ord, V> {
+  Vacant(VacantEntry<'a, K, V>),
+  Occupied(OccupiedEntry<'a, K, V>),
+}
+
+pub struct VacantEntry<'a, K: Ord, V> {
+  key: K,
+  // 插入路径上最深的不平衡节点，见 avl_tree::insert_vacant
+  critical: Option<usize>,
+  map: &'a mut AVLMap<K, V>,
+}
+
+// node 与 map 都代表 entry 对映射的独占借用，node 由 map 派生，
+// 只有 remove_entry 在不再使用 node 之后才会通过 map 重新借用整个映射。
+// 两者都用裸指针保存，按值传入 self 的方法中 node 不会被当作受保护的引用，与 std 的 BTreeMap 相同
+pub struct OccupiedEntry<'a, K: Ord, V> {
+  // 调用者传入的键，remove_entry 用它删除节点
+  key: K,
+  node: NonNull<AVLNode<K, V>>,
+  map: NonNull<AVLMap<K, V>>,
+  marker: PhantomData<&'a mut AVLMap<K, V>>,
+}
+
+impl<'a, K: Ord, V> Entry<'a, K, V> {
+  pub fn key(&self) -> &K {
+    match self {
+      Entry::Vacant(entry) => entry.key(),
+      Entry::Occupied(entry) => entry.key(),
+    }
+  }
+
+  pub fn or_insert(self, default: V) -> &'a mut V {
+    match self {
+      Entry::Vacant(entry) => entry.insert(default),
+      Entry::Occupied(entry) => entry.into_mut(),
+    }
+  }
+
+  pub fn or_insert_with<F: FnOnce() -> V>(self, default: F) -> &'a mut V {
+    match self {
+      Entry::Vacant(entry) => entry.insert(default()),
+      Entry::Occupied(entry) => entry.into_mut(),
+    }
+  }
+
+  pub fn or_insert_with_key<F: FnOnce(&K) -> V>(self, default: F) -> &'a mut V {
+    match self {
+      Entry::Vacant(entry) => {
+        let value = default(&entry.key);
+        entry.insert(value)
+      }
+      Entry::Occupied(entry) => entry.into_mut(),
+    }
+  }
+
+  pub fn and_modify<F: FnOnce(&mut V)>(self, f: F) -> Self {
+    match self {
+      Entry::Vacant(entry) => Entry::Vacant(entry),
+      Entry::Occupied(mut entry) => {
+        f(entry.get_mut());
+        Entry::Occupied(entry)
+      }
+    }
+  }
+}
+
+impl<'a, K: Ord, V: Default> Entry<'a, K, V> {
+  pub fn or_default(self) -> &'a mut V {
+    self.or_insert_with(V::default)
+  }
+}
+
+impl<'a, K: Ord, V> VacantEntry<'a, K, V> {
+  pub fn key(&self) -> &K {
+    &self.key
+  }
+
+  pub fn into_key(self) -> K {
+    self.key
+  }
+
+  pub fn insert(self, value: V) -> &'a mut V {
+    self.map.length += 1;
+    avl_tree::insert_vacant(&mut self.map.root, self.key, value, self.critical, &Natural)
+  }
+}
+
+impl<'a, K: Ord, V> OccupiedEntry<'a, K, V> {
+  // 映射中原有的键
+  pub fn key(&self) -> &K {
+    // SAFETY: 在 entry 的生命周期内节点一直位于映射中，且只能通过 entry 访问
+    unsafe { &self.node.as_ref().key }
+  }
+
+  pub fn get(&self) -> &V {
+    // SAFETY: 同 key
+    unsafe { &self.node.as_ref().value }
+  }
+
+  pub fn get_mut(&mut self) -> &mut V {
+    // SAFETY: 同 key
+    unsafe { &mut self.node.as_mut().value }
+  }
+
+  pub fn into_mut(mut self) -> &'a mut V {
+    // SAFETY: 同 key，entry 被消耗后返回的引用独占映射直到 'a 结束
+    unsafe { &mut self.node.as_mut().value }
+  }
+
+  pub fn insert(&mut self, value: V) -> V {
+    mem::replace(self.get_mut(), value)
+  }
+
+  pub fn remove(self) -> V {
+    self.remove_entry().1
+  }
+
+  pub fn remove_entry(self) -> (K, V) {
+    // SAFETY: node 在此之后不再使用，map 仍然代表 entry 创建时对映射的独占借用
+    let map = unsafe { &mut *self.map.as_ptr() };
+    map.remove_entry(&self.key).unwrap()
+  }
+}
+
+pub struct Iter<'a, K, V, S = ()> {
+  node_stack: Vec<&'a AVLNode<K, V, S>>,
+}
+
+impl<'a, K, V, S> Iter<'a, K, V, S> {
+  fn push_left_path(&mut self, mut child: &'a Option<Box<AVLNode<K, V, S>>>) {
+    while let Some(node) = child {
+      self.node_stack.push(node.as_ref());
+      child = &node.left;
+    }
+  }
+}
+
+impl<'a, K, V, S> Iterator for Iter<'a, K, V, S> {
+  type Item = (&'a K, &'a V);
+  fn next(&mut self) -> Option<Self::Item> {
+    let node = self.node_stack.pop()?;
+    self.push_left_path(&node.right);
+    Some((&node.key, &node.value))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{AVLMap, Entry};
+  use crate::avl_tree::{Max, MaxPrefixSum, Min, Sum};
+  use std::collections::{btree_map, BTreeMap};
+
+  #[test]
+  fn test_avl_map() {
+    let mut map = AVLMap::new();
+    for (i, word) in ["d", "b", "a", "c", "f", "e", "g"].iter().enumerate() {
+      assert_eq!(map.insert(word.to_string(), i), None);
+    }
+    assert_eq!(map.len(), 7);
+    assert_eq!(map.insert("a".to_string(), 10), Some(2));
+    assert_eq!(map.get("a"), Some(&10));
+    *map.get_mut("b").unwrap() += 1;
+    assert_eq!(map.get("b"), Some(&2));
+    assert_eq!(map.remove("d"), Some(0));
+    assert_eq!(map.remove("d"), None);
+    assert!(!map.contains_key("d"));
+
+    // test entry
+    *map.entry("z".to_string()).or_insert(0) += 5;
+    *map.entry("z".to_string()).or_insert(0) += 5;
+    assert_eq!(map.get("z"), Some(&10));
+    map
+      .entry("a".to_string())
+      .and_modify(|v| *v = 0)
+      .or_default();
+    assert_eq!(map.get("a"), Some(&0));
+    if let Entry::Occupied(entry) = map.entry("z".to_string()) {
+      assert_eq!(entry.remove(), 10);
+    }
+    // 大量插入触发旋转后，entry 返回的引用仍然有效
+    let mut counts = AVLMap::new();
+    for i in 0..100 {
+      *counts.entry(i % 37).or_insert(0) += 1;
+    }
+    assert_eq!(counts.len(), 37);
+    assert_eq!(counts.get(&0), Some(&3));
+    // 与 std 的 BTreeMap 对照，条目返回的引用指向被插入或找到的值
+    let mut entries = AVLMap::new();
+    let mut expected = BTreeMap::new();
+    for i in 0..500 {
+      let key = (i * 7919) % 101;
+      match (entries.entry(key), expected.entry(key)) {
+        (Entry::Occupied(entry), btree_map::Entry::Occupied(other)) if i % 3 == 0 => {
+          assert_eq!(entry.key(), other.key());
+          assert_eq!(entry.remove_entry(), other.remove_entry());
+        }
+        (Entry::Occupied(mut entry), btree_map::Entry::Occupied(mut other)) => {
+          assert_eq!(entry.get(), other.get());
+          *entry.get_mut() += 1;
+          *other.get_mut() += 1;
+          assert_eq!(entry.insert(i), other.insert(i));
+          *entry.into_mut() += 1;
+          *other.into_mut() += 1;
+        }
+        (Entry::Vacant(entry), btree_map::Entry::Vacant(other)) => {
+          *entry.insert(i) *= 2;
+          *other.insert(i) *= 2;
+        }
+        _ => panic!("entry for {key} differs from BTreeMap"),
+      }
+    }
+    assert_eq!(entries.len(), expected.len());
+    assert!(entries.iter().eq(expected.iter()));
+
+    let keys: Vec<_> = map.iter().map(|(k, _)| k.as_str()).collect();
+    assert_eq!(keys, vec!["a", "b", "c", "e", "f", "g"]);
+  }
+
+  #[test]
+  fn test_fold_range() {
+    let mut sums: AVLMap<i32, i64, Sum<i64>> = AVLMap::new();
+    let mut mins: AVLMap<i32, i64, Min<i64>> = AVLMap::new();
+    let mut maxs: AVLMap<i32, i64, Max<i64>> = AVLMap::new();
+    let mut prefix: AVLMap<i32, i64, MaxPrefixSum<i64>> = AVLMap::new();
+    let mut values: Vec<i64> = (0..100).map(|i| (i * 37) % 23 - 11).collect();
+    for (i, &value) in values.iter().enumerate() {
+      sums.insert(i as i32, value);
+      mins.insert(i as i32, value);
+      maxs.insert(i as i32, value);
+      prefix.insert(i as i32, value);
+    }
+    // 替换或修改值后，摘要需要沿路径重新计算
+    values[50] = 100;
+    values[7] = -50;
+    for (key, value) in [(50, 100), (7, -50)] {
+      sums.insert(key, value);
+      prefix.insert(key, value);
+      mins.update(&key, |v| *v = value);
+      maxs.update(&key, |v| *v = value);
+    }
+    assert_eq!(sums.update(&1000, |v| *v = 0), None);
+
+    for (a, b) in [(0, 99), (3, 3), (10, 60), (40, 55), (60, 10), (7, 8)] {
+      let range = if a <= b { &values[a..=b] } else { &[] };
+      let (a, b) = (a as i32, b as i32);
+      assert_eq!(sums.fold_range(a..=b).0, range.iter().sum::<i64>());
+      assert_eq!(mins.fold_range(a..=b).0, range.iter().copied().min());
+      assert_eq!(maxs.fold_range(a..=b).0, range.iter().copied().max());
+      let summary = prefix.fold_range(a..=b);
+      let max_prefix = range.iter().scan(0, |acc, v| {
+        *acc += v;
+        Some(*acc)
+      });
+      assert_eq!(summary.sum, range.iter().sum::<i64>());
+      assert_eq!(summary.max_prefix, max_prefix.max().unwrap_or(0).max(0));
+    }
+    assert_eq!(sums.fold_range(..).0, values.iter().sum::<i64>());
+    assert_eq!(sums.fold_range(..0).0, 0);
+    assert_eq!(sums.fold_range(98..).0, values[98] + values[99]);
+  }
+}
@@ -0,0 +1,203 @@
+use std::{borrow::Borrow, iter::FusedIterator, ops::Bound};
+
+use crate::{
+  avl_map::{self, AVLMap},
+  avl_tree::Sum,
+};
+
+// 允许重复元素的有序多重集合，相同元素只占用一个节点并记录出现次数，
+// 子树内出现次数之和作为摘要，用于按重数计算排名
+#[derive(Debug)]
+pub struct AVLMultiSet<T> {
+  map: AVLMap<T, usize, Sum<usize>>,
+  length: usize,
+}
+
+impl<T: Ord> Default for AVLMultiSet<T> {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl<T: Ord> AVLMultiSet<T> {
+  pub fn new() -> Self {
+    Self {
+      map: AVLMap::new(),
+      length: 0,
+    }
+  }
+
+  // 插入元素，元素已存在时只增加出现次数，传入的值被丢弃
+  pub fn insert(&mut self, value: T) {
+    if self.map.update(&value, |count| *count += 1).is_none() {
+      self.map.insert(value, 1);
+    }
+    self.length += 1;
+  }
+
+  // 元素的出现次数
+  pub fn count<Q>(&self, value: &Q) -> usize
+  where
+    T: Borrow<Q>,
+    Q: Ord + ?Sized,
+  {
+    self.map.get(value).copied().unwrap_or(0)
+  }
+
+  pub fn contains<Q>(&self, value: &Q) -> bool
+  where
+    T: Borrow<Q>,
+    Q: Ord + ?Sized,
+  {
+    self.map.contains_key(value)
+  }
+
+  // 删除一个元素，出现次数减为 0 时删除节点
+  pub fn remove_one<Q>(&mut self, value: &Q) -> bool
+  where
+    T: Borrow<Q>,
+    Q: Ord + ?Sized,
+  {
+    let Some(left) = self.map.update(value, |count| {
+      *count -= 1;
+      *count
+    }) else {
+      return false;
+    };
+    if left == 0 {
+      self.map.remove(value);
+    }
+    self.length -= 1;
+    true
+  }
+
+  // 删除元素的所有副本，返回删除的个数
+  pub fn remove_all<Q>(&mut self, value: &Q) -> usize
+  where
+    T: Borrow<Q>,
+    Q: Ord + ?Sized,
+  {
+    let count = self.map.remove(value).unwrap_or(0);
+    self.length -= count;
+    count
+  }
+
+  // 严格小于 value 的元素个数(计入重复)
+  pub fn rank<Q>(&self, value: &Q) -> usize
+  where
+    T: Borrow<Q>,
+    Q: Ord + ?Sized,
+  {
+    self
+      .map
+      .fold_range::<Q, _>((Bound::Unbounded, Bound::Excluded(value)))
+      .0
+  }
+
+  pub fn clear(&mut self) {
+    self.map.clear();
+    self.length = 0;
+  }
+
+  // 元素总数，重复元素分别计数
+  pub fn len(&self) -> usize {
+    self.length
+  }
+
+  // 不同元素的个数
+  pub fn distinct_len(&self) -> usize {
+    self.map.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.length == 0
+  }
+
+  // 按顺序遍历所有元素，重复元素会重复出现
+  pub fn iter(&self) -> Iter<'_, T> {
+    Iter {
+      inner: self.map.iter(),
+      current: None,
+      remaining: self.length,
+    }
+  }
+
+  // 按顺序遍历不同元素及其出现次数
+  pub fn iter_counts(&self) -> impl Iterator<Item = (&T, usize)> {
+    self.map.iter().map(|(value, count)| (value, *count))
+  }
+}
+
+pub struct Iter<'a, T> {
+  inner: avl_map::Iter<'a, T, usize, Sum<usize>>,
+  current: Option<(&'a T, usize)>,
+  // 尚未返回的元素个数，计入重复
+  remaining: usize,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+  type Item = &'a T;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    loop {
+      if let Some((value, remaining)) = &mut self.current {
+        if *remaining > 0 {
+          *remaining -= 1;
+          self.remaining -= 1;
+          return Some(*value);
+        }
+      }
+      let (value, count) = self.inner.next()?;
+      self.current = Some((value, *count));
+    }
+  }
+
+  fn size_hint(&self) -> (usize, Option<usize>) {
+    (self.remaining, Some(self.remaining))
+  }
+}
+
+impl<'a, T> ExactSizeIterator for Iter<'a, T> {}
+
+impl<'a, T> FusedIterator for Iter<'a, T> {}
+
+#[cfg(test)]
+mod tests {
+  use super::AVLMultiSet;
+
+  #[test]
+  fn test_avl_multiset() {
+    let mut scores = AVLMultiSet::new();
+    for score in [90, 85, 90, 70, 85, 90, 100] {
+      scores.insert(score);
+    }
+    assert_eq!(scores.len(), 7);
+    assert_eq!(scores.distinct_len(), 4);
+    assert_eq!(scores.count(&90), 3);
+    assert_eq!(scores.count(&60), 0);
+    assert_eq!(scores.rank(&90), 3);
+    assert_eq!(scores.rank(&101), 7);
+    assert_eq!(
+      scores.iter().copied().collect::<Vec<_>>(),
+      vec![70, 85, 85, 90, 90, 90, 100]
+    );
+    let mut iter = scores.iter();
+    assert_eq!(iter.len(), 7);
+    iter.nth(3);
+    assert_eq!(iter.size_hint(), (3, Some(3)));
+
+    assert!(scores.remove_one(&90));
+    assert_eq!(scores.count(&90), 2);
+    assert!(scores.remove_one(&70));
+    assert!(!scores.contains(&70));
+    assert!(!scores.remove_one(&70));
+    assert_eq!(scores.remove_all(&90), 2);
+    assert_eq!(scores.remove_all(&90), 0);
+    assert_eq!(scores.len(), 3);
+    assert_eq!(scores.rank(&100), 2);
+    assert_eq!(
+      scores.iter_counts().collect::<Vec<_>>(),
+      vec![(&85, 2), (&100, 1)]
+    );
+  }
+}
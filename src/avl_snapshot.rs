@@ -0,0 +1,179 @@
+use std::{
+  error::Error,
+  fmt::{self, Display},
+};
+
+use crate::{
+  avl_tree::{AVLTree, Comparator},
+  codec::{crc32, read_varint, write_varint, Codec},
+};
+
+// 快照格式(小端)：
+//   magic "AVLS" | version: u16 | 元素个数: varint | 元素... | crc32: u32
+// 元素按树的顺序依次用 Codec 编码，末尾的 CRC-32 覆盖之前的所有字节
+const MAGIC: &[u8; 4] = b"AVLS";
+const VERSION: u16 = 1;
+const HEADER_LEN: usize = MAGIC.len() + 2;
+const CHECKSUM_LEN: usize = 4;
+
+// 读取快照失败的原因
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SnapshotError {
+  // 开头不是快照的 magic
+  BadMagic,
+  UnsupportedVersion { version: u16 },
+  // 数据在读完声明的内容前结束
+  Truncated,
+  ChecksumMismatch { stored: u32, actual: u32 },
+  // 第 index 个元素无法解码
+  InvalidValue { index: usize },
+  // 第 index 个元素不大于前一个元素
+  OutOfOrder { index: usize },
+  // 元素之后、校验和之前还有多余的字节
+  TrailingBytes,
+}
+
+impl Display for SnapshotError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      SnapshotError::BadMagic => write!(f, "not an AVLTree snapshot"),
+      SnapshotError::UnsupportedVersion { version } => {
+        write!(f, "unsupported snapshot version {}", version)
+      }
+      SnapshotError::Truncated => write!(f, "snapshot is truncated"),
+      SnapshotError::ChecksumMismatch { stored, actual } => write!(
+        f,
+        "snapshot checksum {:08x} does not match computed {:08x}",
+        stored, actual
+      ),
+      SnapshotError::InvalidValue { index } => write!(f, "element {} cannot be decoded", index),
+      SnapshotError::OutOfOrder { index } => write!(f, "element {} is out of order", index),
+      SnapshotError::TrailingBytes => write!(f, "snapshot has trailing bytes"),
+    }
+  }
+}
+
+impl Error for SnapshotError {}
+
+impl<T: Codec, C: Comparator<T>> AVLTree<T, C> {
+  // 按树的顺序写出带版本号与校验和的二进制快照
+  pub fn to_snapshot(&self) -> Vec<u8> {
+    let mut out = Vec::with_capacity(HEADER_LEN + CHECKSUM_LEN + self.size());
+    out.extend_from_slice(MAGIC);
+    out.extend_from_slice(&VERSION.to_le_bytes());
+    write_varint(&mut out, self.size() as u64);
+    for val in self.iter() {
+      val.encode(&mut out);
+    }
+    let checksum = crc32(&out);
+    out.extend_from_slice(&checksum.to_le_bytes());
+    out
+  }
+
+  // 校验快照后以线性时间批量构建，元素须按 cmp 的顺序严格递增
+  pub fn from_snapshot_with(bytes: &[u8], cmp: C) -> Result<Self, SnapshotError> {
+    if bytes.len() < HEADER_LEN {
+      return Err(SnapshotError::Truncated);
+    }
+    if &bytes[..MAGIC.len()] != MAGIC {
+      return Err(SnapshotError::BadMagic);
+    }
+    let version = u16::from_le_bytes([bytes[4], bytes[5]]);
+    if version != VERSION {
+      return Err(SnapshotError::UnsupportedVersion { version });
+    }
+    if bytes.len() < HEADER_LEN + CHECKSUM_LEN {
+      return Err(SnapshotError::Truncated);
+    }
+    let (body, checksum) = bytes.split_at(bytes.len() - CHECKSUM_LEN);
+    let stored = u32::from_le_bytes(checksum.try_into().unwrap());
+    let actual = crc32(body);
+    if stored != actual {
+      return Err(SnapshotError::ChecksumMismatch { stored, actual });
+    }
+
+    let mut input = &body[HEADER_LEN..];
+    let count = read_varint(&mut input).ok_or(SnapshotError::Truncated)?;
+    let count = usize::try_from(count).map_err(|_| SnapshotError::Truncated)?;
+    // 每个元素至少占一个字节，避免按损坏的个数分配过大的内存
+    let mut vals = Vec::with_capacity(count.min(input.len()));
+    for index in 0..count {
+      if input.is_empty() {
+        return Err(SnapshotError::Truncated);
+      }
+      let val = T::decode(&mut input).ok_or(SnapshotError::InvalidValue { index })?;
+      if let Some((last, _)) = vals.last() {
+        if cmp.compare(last, &val).is_ge() {
+          return Err(SnapshotError::OutOfOrder { index });
+        }
+      }
+      vals.push((val, ()));
+    }
+    if !input.is_empty() {
+      return Err(SnapshotError::TrailingBytes);
+    }
+    Ok(Self::from_sorted_vec(vals, cmp))
+  }
+}
+
+impl<T: Codec, C: Comparator<T> + Default> AVLTree<T, C> {
+  pub fn from_snapshot(bytes: &[u8]) -> Result<Self, SnapshotError> {
+    Self::from_snapshot_with(bytes, C::default())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::SnapshotError;
+  use crate::avl_tree::{AVLTree, Reversed};
+
+  #[test]
+  fn test_snapshot() {
+    let avl_tree: AVLTree<i64> = (-500..500).map(|i| i * 1000).collect();
+    let bytes = avl_tree.to_snapshot();
+    let decoded = AVLTree::<i64>::from_snapshot(&bytes).unwrap();
+    decoded.validate().unwrap();
+    assert_eq!(decoded, avl_tree);
+
+    let words: AVLTree<String, Reversed> = ["b", "a", "c"].iter().map(|s| s.to_string()).collect();
+    let decoded = AVLTree::<String, Reversed>::from_snapshot(&words.to_snapshot()).unwrap();
+    assert_eq!(decoded.first().map(String::as_str), Some("c"));
+    assert_eq!(
+      AVLTree::<i32>::from_snapshot(&AVLTree::<i32>::new().to_snapshot())
+        .unwrap()
+        .size(),
+      0
+    );
+
+    // 破坏数据后应当被校验和发现
+    let mut corrupted = bytes.clone();
+    corrupted[10] ^= 1;
+    assert!(matches!(
+      AVLTree::<i64>::from_snapshot(&corrupted),
+      Err(SnapshotError::ChecksumMismatch { .. })
+    ));
+    assert!(matches!(
+      AVLTree::<i64>::from_snapshot(&bytes[..bytes.len() - 1]),
+      Err(SnapshotError::ChecksumMismatch { .. })
+    ));
+    assert_eq!(
+      AVLTree::<i64>::from_snapshot(b"AVL").unwrap_err(),
+      SnapshotError::Truncated
+    );
+    assert_eq!(
+      AVLTree::<i64>::from_snapshot(b"BTRE\x01\x00").unwrap_err(),
+      SnapshotError::BadMagic
+    );
+    let mut future = bytes.clone();
+    future[4] = 2;
+    assert_eq!(
+      AVLTree::<i64>::from_snapshot(&future).unwrap_err(),
+      SnapshotError::UnsupportedVersion { version: 2 }
+    );
+    // 用另一种顺序读取时元素乱序
+    assert_eq!(
+      AVLTree::<i64, Reversed>::from_snapshot(&bytes).unwrap_err(),
+      SnapshotError::OutOfOrder { index: 1 }
+    );
+  }
+}
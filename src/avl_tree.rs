@@ -1,11 +1,16 @@
 use std::{
+  borrow::Borrow,
   cmp::{max, Ordering},
-  fmt::Debug,
+  error::Error,
+  fmt::{self, Debug, Display},
+  hash::{Hash, Hasher},
+  iter::FusedIterator,
   mem,
-  ops::Not,
+  ops::{Add, Bound, Not, RangeBounds},
+  vec,
 };
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, PartialEq, Eq)]
 enum Side {
   Left,
   Right,
@@ -22,37 +27,175 @@ impl Not for Side {
   }
 }
 
-#[derive(Debug)]
-struct AVLNode<T: Ord> {
-  val: T,
-  left: Option<Box<AVLNode<T>>>,
-  right: Option<Box<AVLNode<T>>>,
-  height: usize,
+// 比较器，决定树中元素的排列顺序
+pub trait Comparator<T: ?Sized> {
+  fn compare(&self, a: &T, b: &T) -> Ordering;
 }
 
-impl<T: Ord> AVLNode<T> {
-  // 平衡当前节点子树
-  fn rebalance(&mut self) {
-    self.update_height();
-    let side = match self.balance_factor() {
-      -2 => Side::Left,
-      2 => Side::Right,
-      _ => return,
-    };
-    let subtree = self.child_mut(side).as_mut().unwrap();
-    if let (Side::Left, 1) | (Side::Right, -1) = (side, subtree.balance_factor()) {
-      subtree.rotate(side);
+// 闭包可以直接作为比较器使用
+impl<T: ?Sized, F: Fn(&T, &T) -> Ordering> Comparator<T> for F {
+  fn compare(&self, a: &T, b: &T) -> Ordering {
+    self(a, b)
+  }
+}
+
+// 自然顺序，即 T::cmp
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Natural;
+
+impl<T: Ord + ?Sized> Comparator<T> for Natural {
+  fn compare(&self, a: &T, b: &T) -> Ordering {
+    a.cmp(b)
+  }
+}
+
+// 与自然顺序相反
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Reversed;
+
+impl<T: Ord + ?Sized> Comparator<T> for Reversed {
+  fn compare(&self, a: &T, b: &T) -> Ordering {
+    b.cmp(a)
+  }
+}
+
+// 按 key 函数提取出的键的自然顺序比较
+#[derive(Debug, Clone, Copy)]
+pub struct ByKey<F>(pub F);
+
+impl<T: ?Sized, K: Ord, F: Fn(&T) -> K> Comparator<T> for ByKey<F> {
+  fn compare(&self, a: &T, b: &T) -> Ordering {
+    (self.0)(a).cmp(&(self.0)(b))
+  }
+}
+
+/// 子树摘要(幺半群)，由子树内所有键值对按中序通过 combine 合并得到，
+/// combine 需满足结合律且以 empty 为单位元，但不要求交换律。
+/// 节点在 update_height 中重新计算摘要，因此旋转后摘要始终与子树一致
+pub trait Summary<K, V>: Clone {
+  fn empty() -> Self;
+  fn single(key: &K, value: &V) -> Self;
+  fn combine(left: &Self, right: &Self) -> Self;
+}
+
+// 不需要摘要时使用 ()，不占用空间
+impl<K, V> Summary<K, V> for () {
+  fn empty() -> Self {}
+  fn single(_key: &K, _value: &V) -> Self {}
+  fn combine(_left: &Self, _right: &Self) -> Self {}
+}
+
+/// 值之和
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Sum<V>(pub V);
+
+impl<K, V: Default + Clone + Add<Output = V>> Summary<K, V> for Sum<V> {
+  fn empty() -> Self {
+    Sum(V::default())
+  }
+
+  fn single(_key: &K, value: &V) -> Self {
+    Sum(value.clone())
+  }
+
+  fn combine(left: &Self, right: &Self) -> Self {
+    Sum(left.0.clone() + right.0.clone())
+  }
+}
+
+/// 值的最小值，空区间为 None
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Min<V>(pub Option<V>);
+
+impl<K, V: Ord + Clone> Summary<K, V> for Min<V> {
+  fn empty() -> Self {
+    Min(None)
+  }
+
+  fn single(_key: &K, value: &V) -> Self {
+    Min(Some(value.clone()))
+  }
+
+  fn combine(left: &Self, right: &Self) -> Self {
+    match (&left.0, &right.0) {
+      (Some(a), Some(b)) => Min(Some(a.min(b).clone())),
+      (a, b) => Min(a.as_ref().or(b.as_ref()).cloned()),
     }
-    self.rotate(!side);
   }
-  // 更新节点高度
-  fn update_height(&mut self) {
-    self.height = 1 + max(self.height(Side::Left), self.height(Side::Right))
+}
+
+/// 值的最大值，空区间为 None
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Max<V>(pub Option<V>);
+
+impl<K, V: Ord + Clone> Summary<K, V> for Max<V> {
+  fn empty() -> Self {
+    Max(None)
+  }
+
+  fn single(_key: &K, value: &V) -> Self {
+    Max(Some(value.clone()))
+  }
+
+  fn combine(left: &Self, right: &Self) -> Self {
+    match (&left.0, &right.0) {
+      (Some(a), Some(b)) => Max(Some(a.max(b).clone())),
+      (a, b) => Max(a.as_ref().or(b.as_ref()).cloned()),
+    }
+  }
+}
+
+/// 按键顺序累加值时的总和与最大前缀和，前缀包含空前缀，因此 max_prefix 不小于 V::default()
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MaxPrefixSum<V> {
+  pub sum: V,
+  pub max_prefix: V,
+}
+
+impl<K, V: Ord + Default + Clone + Add<Output = V>> Summary<K, V> for MaxPrefixSum<V> {
+  fn empty() -> Self {
+    Self::default()
+  }
+
+  fn single(_key: &K, value: &V) -> Self {
+    Self {
+      sum: value.clone(),
+      max_prefix: value.clone().max(V::default()),
+    }
+  }
+
+  fn combine(left: &Self, right: &Self) -> Self {
+    Self {
+      sum: left.sum.clone() + right.sum.clone(),
+      max_prefix: left
+        .max_prefix
+        .clone()
+        .max(left.sum.clone() + right.max_prefix.clone()),
+    }
   }
+}
+
+// 节点按 key 排序，value 为附带的数据，集合使用 () 作为 value，summary 为子树摘要
+#[derive(Debug, Clone)]
+pub(crate) struct AVLNode<K, V, S = ()> {
+  pub(crate) key: K,
+  pub(crate) value: V,
+  pub(crate) left: Option<Box<AVLNode<K, V, S>>>,
+  pub(crate) right: Option<Box<AVLNode<K, V, S>>>,
+  pub(crate) height: usize,
+  pub(crate) size: usize, // 子树节点数，用于顺序统计
+  pub(crate) summary: S,
+}
+
+impl<K, V, S> AVLNode<K, V, S> {
   // 高度计算
   fn height(&self, side: Side) -> usize {
     self.child(side).as_ref().map_or(0, |n| n.height)
   }
+  // 子树大小计算
+  fn size(&self, side: Side) -> usize {
+    self.child(side).as_ref().map_or(0, |n| n.size)
+  }
   // 平衡因子计算
   fn balance_factor(&self) -> i8 {
     let left = self.height(Side::Left);
@@ -64,21 +207,60 @@ impl<T: Ord> AVLNode<T> {
     }
   }
   // 获取子树不可变引用
-  fn child(&self, side: Side) -> &Option<Box<AVLNode<T>>> {
+  fn child(&self, side: Side) -> &Option<Box<AVLNode<K, V, S>>> {
     match side {
       Side::Left => &self.left,
       Side::Right => &self.right,
     }
   }
   // 获取子树可变引用
-  fn child_mut(&mut self, side: Side) -> &mut Option<Box<AVLNode<T>>> {
+  fn child_mut(&mut self, side: Side) -> &mut Option<Box<AVLNode<K, V, S>>> {
     match side {
       Side::Left => &mut self.left,
       Side::Right => &mut self.right,
     }
   }
-  // 旋转操作
-  fn rotate(&mut self, side: Side) {
+}
+
+impl<K, V, S: Summary<K, V>> AVLNode<K, V, S> {
+  pub(crate) fn new(key: K, value: V) -> Box<Self> {
+    Box::new(Self {
+      summary: S::single(&key, &value),
+      key,
+      value,
+      left: None,
+      right: None,
+      height: 1,
+      size: 1,
+    })
+  }
+
+  // 平衡当前节点子树
+  fn rebalance(self: &mut Box<Self>) {
+    self.update_height();
+    let side = match self.balance_factor() {
+      -2 => Side::Left,
+      2 => Side::Right,
+      _ => return,
+    };
+    let subtree = self.child_mut(side).as_mut().unwrap();
+    if let (Side::Left, 1) | (Side::Right, -1) = (side, subtree.balance_factor()) {
+      subtree.rotate(side);
+    }
+    self.rotate(!side);
+  }
+  // 更新节点高度、子树大小与子树摘要
+  fn update_height(&mut self) {
+    self.height = 1 + max(self.height(Side::Left), self.height(Side::Right));
+    self.size = 1 + self.size(Side::Left) + self.size(Side::Right);
+    let empty = S::empty();
+    let left = self.left.as_ref().map_or(&empty, |n| &n.summary);
+    let right = self.right.as_ref().map_or(&empty, |n| &n.summary);
+    let node = S::combine(left, &S::single(&self.key, &self.value));
+    self.summary = S::combine(&node, right);
+  }
+  // 旋转操作，交换的是 Box 指针而不是节点内容，节点在堆上的位置保持不变
+  fn rotate(self: &mut Box<Self>, side: Side) {
     let mut subtree = self.child_mut(!side).take().unwrap();
     *self.child_mut(!side) = subtree.child_mut(side).take();
     self.update_height();
@@ -88,168 +270,1766 @@ impl<T: Ord> AVLNode<T> {
   }
 }
 
-#[derive(Debug)]
-pub struct AVLTree<T: Ord> {
-  root: Option<Box<AVLNode<T>>>,
+// 元素的顺序由比较器 C 决定，默认使用自然顺序
+#[derive(Clone)]
+pub struct AVLTree<T, C = Natural> {
+  root: Option<Box<AVLNode<T, ()>>>,
   length: usize,
+  cmp: C,
+}
+
+impl<T, C: Default> Default for AVLTree<T, C> {
+  fn default() -> Self {
+    Self::with_comparator(C::default())
+  }
 }
 
 impl<T: Ord> AVLTree<T> {
   pub fn new() -> Self {
+    Self::with_comparator(Natural)
+  }
+
+  // 按自然顺序的逆序排列
+  pub fn reversed() -> AVLTree<T, Reversed> {
+    AVLTree::with_comparator(Reversed)
+  }
+
+  // 由升序序列在线性时间内构造完全平衡的树，相邻的重复元素只保留第一个，
+  // 出现降序元素时返回 OutOfOrder，其中 index 为该元素在序列中的位置
+  pub fn from_sorted_iter<I: IntoIterator<Item = T>>(iter: I) -> Result<Self, OutOfOrder> {
+    Self::from_sorted_iter_with(iter, Natural)
+  }
+}
+
+impl<T> AVLTree<T> {
+  // 按 key 函数提取出的键排列，键相同的元素视为重复
+  pub fn by_key<K: Ord, F: Fn(&T) -> K>(key: F) -> AVLTree<T, ByKey<F>> {
+    AVLTree::with_comparator(ByKey(key))
+  }
+}
+
+impl<T, C> AVLTree<T, C> {
+  pub fn with_comparator(cmp: C) -> Self {
     Self {
       root: None,
       length: 0,
+      cmp,
+    }
+  }
+
+  pub fn comparator(&self) -> &C {
+    &self.cmp
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.length == 0
+  }
+
+  pub fn size(&self) -> usize {
+    self.length
+  }
+}
+
+impl<T: Debug, C> AVLTree<T, C> {
+  // 以目录树形式输出树结构，每个节点附带高度与子树大小
+  pub fn pretty(&self) -> String {
+    let mut out = String::new();
+    if let Some(root) = &self.root {
+      pretty_node(root, "", "", "", &mut out);
+    }
+    out
+  }
+
+  // 导出 Graphviz DOT 格式的节点结构
+  pub fn to_dot(&self) -> String {
+    let mut out = String::from("digraph AVLTree {\n");
+    if let Some(root) = &self.root {
+      dot_node(root, &mut 0, &mut out);
+    }
+    out.push_str("}\n");
+    out
+  }
+}
+
+impl<T, C: Comparator<T>> AVLTree<T, C> {
+  // 检查有序性、高度、平衡因子、子树大小与 length 是否一致
+  pub fn validate(&self) -> Result<(), AVLViolation> {
+    let (_, actual) = validate(&self.root, &self.cmp, &mut None, &mut 0)?;
+    if actual != self.length {
+      return Err(AVLViolation::LengthMismatch {
+        length: self.length,
+        actual,
+      });
+    }
+    Ok(())
+  }
+
+  // 与 from_sorted_iter 相同，序列须按 cmp 的顺序排列
+  pub fn from_sorted_iter_with<I: IntoIterator<Item = T>>(
+    iter: I,
+    cmp: C,
+  ) -> Result<Self, OutOfOrder> {
+    let mut vals: Vec<(T, ())> = Vec::new();
+    for (index, val) in iter.into_iter().enumerate() {
+      match vals.last().map(|(last, _)| cmp.compare(last, &val)) {
+        Some(Ordering::Greater) => return Err(OutOfOrder { index }),
+        Some(Ordering::Equal) => continue,
+        _ => vals.push((val, ())),
+      }
+    }
+    Ok(Self::from_sorted_vec(vals, cmp))
+  }
+
+  // vals 必须已经严格升序
+  pub(crate) fn from_sorted_vec(vals: Vec<(T, ())>, cmp: C) -> Self {
+    let length = vals.len();
+    Self {
+      root: build_sorted(&mut vals.into_iter(), length),
+      length,
+      cmp,
     }
   }
 
   pub fn insert(&mut self, val: T) -> bool {
-    let inserted = insert(&mut self.root, val);
+    let inserted = insert(&mut self.root, val, (), &self.cmp).is_ok();
     if inserted {
       self.length += 1
     }
     inserted
   }
 
-  pub fn remove(&mut self, val: T) -> bool {
-    let removed = remove(&mut self.root, val);
-    if removed {
+  pub fn contains<Q>(&self, val: &Q) -> bool
+  where
+    T: Borrow<Q>,
+    Q: ?Sized,
+    C: Comparator<Q>,
+  {
+    get(&self.root, val, &self.cmp).is_some()
+  }
+
+  // 返回树中与 val 相等的元素
+  pub fn get<Q>(&self, val: &Q) -> Option<&T>
+  where
+    T: Borrow<Q>,
+    Q: ?Sized,
+    C: Comparator<Q>,
+  {
+    get(&self.root, val, &self.cmp).map(|node| &node.key)
+  }
+
+  pub fn remove<Q>(&mut self, val: &Q) -> bool
+  where
+    T: Borrow<Q>,
+    Q: ?Sized,
+    C: Comparator<Q>,
+  {
+    self.take(val).is_some()
+  }
+
+  // 删除并返回树中与 val 相等的元素
+  pub fn take<Q>(&mut self, val: &Q) -> Option<T>
+  where
+    T: Borrow<Q>,
+    Q: ?Sized,
+    C: Comparator<Q>,
+  {
+    let removed = remove(&mut self.root, val, &self.cmp).map(|(key, _)| key);
+    if removed.is_some() {
       self.length -= 1
     }
     removed
   }
 
-  pub fn is_empty(&self) -> bool {
-    self.length == 0
+  // 返回小于 val 的元素个数，即 val 在树中的排名(从 0 开始)
+  pub fn rank<Q>(&self, val: &Q) -> usize
+  where
+    T: Borrow<Q>,
+    Q: ?Sized,
+    C: Comparator<Q>,
+  {
+    rank(&self.root, val, false, &self.cmp)
   }
 
-  pub fn size(&self) -> usize {
-    self.length
+  // 返回第 k 小的元素(从 0 开始)
+  pub fn select(&self, k: usize) -> Option<&T> {
+    select(&self.root, k).map(|node| &node.key)
   }
 
-  pub fn iter(&self) -> Iter<T> {
-    let cap = self.root.as_ref().map_or(0, |n| n.height);
-    let mut node_stack = Vec::with_capacity(cap);
+  // 统计落在 range 内的元素个数
+  pub fn count_in_range<Q, R>(&self, range: R) -> usize
+  where
+    T: Borrow<Q>,
+    Q: ?Sized,
+    C: Comparator<Q>,
+    R: RangeBounds<Q>,
+  {
+    self
+      .count_within(range.end_bound())
+      .saturating_sub(self.count_below(range.start_bound()))
+  }
 
-    let mut child = &self.root;
-    while let Some(node) = child {
-      node_stack.push(node.as_ref());
-      child = &node.left;
+  // 统计不满足下界 start 的元素个数
+  fn count_below<Q>(&self, start: Bound<&Q>) -> usize
+  where
+    T: Borrow<Q>,
+    Q: ?Sized,
+    C: Comparator<Q>,
+  {
+    match start {
+      Bound::Included(start) => rank(&self.root, start, false, &self.cmp),
+      Bound::Excluded(start) => rank(&self.root, start, true, &self.cmp),
+      Bound::Unbounded => 0,
     }
+  }
 
-    Iter { node_stack }
+  // 统计满足上界 end 的元素个数
+  fn count_within<Q>(&self, end: Bound<&Q>) -> usize
+  where
+    T: Borrow<Q>,
+    Q: ?Sized,
+    C: Comparator<Q>,
+  {
+    match end {
+      Bound::Included(end) => rank(&self.root, end, true, &self.cmp),
+      Bound::Excluded(end) => rank(&self.root, end, false, &self.cmp),
+      Bound::Unbounded => self.length,
+    }
   }
-}
 
-fn insert<T: Ord>(tree: &mut Option<Box<AVLNode<T>>>, val: T) -> bool {
-  if let Some(node) = tree {
-    let inserted = match val.cmp(&node.val) {
-      Ordering::Equal => false,
-      Ordering::Less => insert(&mut node.left, val),
-      Ordering::Greater => insert(&mut node.right, val),
+  pub fn first(&self) -> Option<&T> {
+    extreme(&self.root, Side::Left).map(|node| &node.key)
+  }
+
+  pub fn last(&self) -> Option<&T> {
+    extreme(&self.root, Side::Right).map(|node| &node.key)
+  }
+
+  pub fn pop_first(&mut self) -> Option<T> {
+    let node = take_min(&mut self.root)?;
+    self.length -= 1;
+    Some(node.key)
+  }
+
+  pub fn pop_last(&mut self) -> Option<T> {
+    let node = take_max(&mut self.root)?;
+    self.length -= 1;
+    Some(node.key)
+  }
+
+  // 小于等于 val 的最大元素
+  pub fn floor<Q>(&self, val: &Q) -> Option<&T>
+  where
+    T: Borrow<Q>,
+    Q: ?Sized,
+    C: Comparator<Q>,
+  {
+    nearest(&self.root, val, Side::Left, true, &self.cmp).map(|node| &node.key)
+  }
+
+  // 大于等于 val 的最小元素
+  pub fn ceiling<Q>(&self, val: &Q) -> Option<&T>
+  where
+    T: Borrow<Q>,
+    Q: ?Sized,
+    C: Comparator<Q>,
+  {
+    nearest(&self.root, val, Side::Right, true, &self.cmp).map(|node| &node.key)
+  }
+
+  // 严格小于 val 的最大元素
+  pub fn predecessor<Q>(&self, val: &Q) -> Option<&T>
+  where
+    T: Borrow<Q>,
+    Q: ?Sized,
+    C: Comparator<Q>,
+  {
+    nearest(&self.root, val, Side::Left, false, &self.cmp).map(|node| &node.key)
+  }
+
+  // 严格大于 val 的最小元素
+  pub fn successor<Q>(&self, val: &Q) -> Option<&T>
+  where
+    T: Borrow<Q>,
+    Q: ?Sized,
+    C: Comparator<Q>,
+  {
+    nearest(&self.root, val, Side::Right, false, &self.cmp).map(|node| &node.key)
+  }
+
+  // 拆分出所有大于等于 val 的元素作为新树返回，原树保留小于 val 的元素
+  pub fn split_off<Q>(&mut self, val: &Q) -> Self
+  where
+    T: Borrow<Q>,
+    Q: ?Sized,
+    C: Comparator<Q> + Clone,
+  {
+    let (less, found, greater) = split(self.root.take(), val, &self.cmp);
+    let greater = match found {
+      Some(node) => Some(join(None, node, greater)),
+      None => greater,
     };
-    // 如果插入新节点，需要重新平衡
-    if inserted {
-      node.rebalance();
+    self.set_root(less);
+    Self::from_root(greater, self.cmp.clone())
+  }
+
+  // 将 other 中的所有元素移入当前树，other 变为空树；与 std 的 BTreeMap::append 相同，
+  // 比较相等的元素保留 other 中的那个
+  pub fn append(&mut self, other: &mut Self) {
+    let merged = union(other.root.take(), self.root.take(), &self.cmp);
+    self.set_root(merged);
+    other.length = 0;
+  }
+
+  // 并集，基于 split/join 实现，不需要逐个重新插入元素，两棵树按当前树的比较器排列
+  pub fn union(self, other: Self) -> Self {
+    let root = union(self.root, other.root, &self.cmp);
+    Self::from_root(root, self.cmp)
+  }
+
+  // 交集
+  pub fn intersection(self, other: Self) -> Self {
+    let root = intersection(self.root, other.root, &self.cmp);
+    Self::from_root(root, self.cmp)
+  }
+
+  // 差集，保留当前树中不在 other 中的元素
+  pub fn difference(self, other: Self) -> Self {
+    let root = difference(self.root, other.root, &self.cmp);
+    Self::from_root(root, self.cmp)
+  }
+
+  fn from_root(root: Option<Box<AVLNode<T, ()>>>, cmp: C) -> Self {
+    let mut tree = Self::with_comparator(cmp);
+    tree.set_root(root);
+    tree
+  }
+
+  // 替换根节点，元素个数取自根节点维护的子树大小
+  fn set_root(&mut self, root: Option<Box<AVLNode<T, ()>>>) {
+    self.length = root.as_ref().map_or(0, |n| n.size);
+    self.root = root;
+  }
+
+  // 清空树并按升序返回所有元素
+  pub fn drain(&mut self) -> IntoIter<T> {
+    let mut vals = Vec::with_capacity(self.length);
+    flatten(self.root.take(), &mut vals);
+    self.length = 0;
+    IntoIter {
+      inner: vals.into_iter(),
+    }
+  }
+
+  // 只保留满足 pred 的元素
+  pub fn retain<F: FnMut(&T) -> bool>(&mut self, mut pred: F) {
+    let mut vals = Vec::with_capacity(self.length);
+    flatten(self.root.take(), &mut vals);
+    vals.retain(|(val, _)| pred(val));
+    self.length = vals.len();
+    self.root = build_sorted(&mut vals.into_iter(), self.length);
+  }
+
+  pub fn clear(&mut self) {
+    self.root = None;
+    self.length = 0;
+  }
+
+  pub fn iter(&self) -> Iter<'_, T> {
+    Iter {
+      raw: RawIter::new::<T, _>(
+        &self.root,
+        Bound::Unbounded,
+        Bound::Unbounded,
+        self.length,
+        &self.cmp,
+      ),
+    }
+  }
+
+  // 按升序遍历落在 range 内的元素，可从两端迭代
+  pub fn range<Q, R>(&self, range: R) -> Range<'_, T>
+  where
+    T: Borrow<Q>,
+    Q: ?Sized,
+    C: Comparator<Q>,
+    R: RangeBounds<Q>,
+  {
+    let (start, end) = (range.start_bound(), range.end_bound());
+    let remaining = self
+      .count_within(end)
+      .saturating_sub(self.count_below(start));
+    Range {
+      raw: RawIter::new(&self.root, start, end, remaining, &self.cmp),
+    }
+  }
+
+  // 返回指向第一个满足下界 bound 的元素的游标，不存在时指向末尾的空位置
+  pub fn lower_bound<Q>(&self, bound: Bound<&Q>) -> Cursor<'_, T, C>
+  where
+    T: Borrow<Q>,
+    Q: ?Sized,
+    C: Comparator<Q>,
+  {
+    Cursor {
+      index: self.count_below(bound),
+      tree: self,
+    }
+  }
+
+  // 返回指向最后一个满足上界 bound 的元素的游标，不存在时指向末尾的空位置
+  pub fn upper_bound<Q>(&self, bound: Bound<&Q>) -> Cursor<'_, T, C>
+  where
+    T: Borrow<Q>,
+    Q: ?Sized,
+    C: Comparator<Q>,
+  {
+    Cursor {
+      index: self
+        .count_within(bound)
+        .checked_sub(1)
+        .unwrap_or(self.length),
+      tree: self,
+    }
+  }
+
+  pub fn lower_bound_mut<Q>(&mut self, bound: Bound<&Q>) -> CursorMut<'_, T, C>
+  where
+    T: Borrow<Q>,
+    Q: ?Sized,
+    C: Comparator<Q>,
+  {
+    CursorMut {
+      index: self.count_below(bound),
+      tree: self,
+    }
+  }
+
+  pub fn upper_bound_mut<Q>(&mut self, bound: Bound<&Q>) -> CursorMut<'_, T, C>
+  where
+    T: Borrow<Q>,
+    Q: ?Sized,
+    C: Comparator<Q>,
+  {
+    CursorMut {
+      index: self
+        .count_within(bound)
+        .checked_sub(1)
+        .unwrap_or(self.length),
+      tree: self,
     }
-    inserted
-  } else {
-    // 如果树为空，直接插入
-    *tree = Some(Box::new(AVLNode {
-      val,
-      height: 1,
-      left: None,
-      right: None,
-    }));
-    true
   }
 }
 
-fn remove<T: Ord>(tree: &mut Option<Box<AVLNode<T>>>, val: T) -> bool {
+/// 按中序将节点的键值对依次移入 out
+pub(crate) fn flatten<K, V, S>(tree: Option<Box<AVLNode<K, V, S>>>, out: &mut Vec<(K, V)>) {
   if let Some(node) = tree {
-    let removed = match val.cmp(&node.val) {
-      Ordering::Less => remove(&mut node.left, val),
-      Ordering::Greater => remove(&mut node.right, val),
-      Ordering::Equal => {
-        *tree = match (node.left.take(), node.right.take()) {
-          // 叶子节点直接删除
-          (None, None) => None,
-          // 只有一个子树，直接替换
-          (Some(left), None) => Some(left),
-          (None, Some(right)) => Some(right),
-          // 有两个子树，找到右子树最小节点替换
-          (Some(left), Some(right)) => Some(merge(left, right)),
-        };
-        return true;
-      }
-    };
-    if removed {
-      node.rebalance();
-    }
-    removed
-  } else {
-    // 如果树为空，直接返回false
-    false
+    let AVLNode {
+      key,
+      value,
+      left,
+      right,
+      ..
+    } = *node;
+    flatten(left, out);
+    out.push((key, value));
+    flatten(right, out);
   }
 }
 
-/// 合并两个子树
-fn merge<T: Ord>(left: Box<AVLNode<T>>, right: Box<AVLNode<T>>) -> Box<AVLNode<T>> {
-  let mut op_right = Some(right);
-  // 合并后的子树根节点为右子树最小节点
-  let mut root = take_min(&mut op_right).unwrap();
-  // 左子树为原左子树，右子树为删除最小节点后平衡的右子树
-  root.left = Some(left);
-  root.right = op_right;
-  // 重新平衡
-  root.rebalance();
-  root
+/// 从升序序列中依次取出 n 个键值对构造完全平衡的子树，左右子树大小至多相差 1
+pub(crate) fn build_sorted<K, V, S: Summary<K, V>>(
+  items: &mut impl Iterator<Item = (K, V)>,
+  n: usize,
+) -> Option<Box<AVLNode<K, V, S>>> {
+  if n == 0 {
+    return None;
+  }
+  let left = build_sorted(items, n / 2);
+  let (key, value) = items.next()?;
+  let mut node = AVLNode::new(key, value);
+  node.left = left;
+  node.right = build_sorted(items, n - 1 - n / 2);
+  node.update_height();
+  Some(node)
 }
 
-/// 取出最小节点
-fn take_min<T: Ord>(tree: &mut Option<Box<AVLNode<T>>>) -> Option<Box<AVLNode<T>>> {
-  if let Some(mut node) = tree.take() {
-    if let Some(small) = take_min(&mut node.left) {
-      // 回溯时，需要重新平衡
+// 根到当前位置路径上的节点及下降的方向。下降时把子树从父节点中取出，
+// 路径上的节点都由栈独占，回溯时再自底向上挂回父节点
+type Path<K, V, S> = Vec<(Box<AVLNode<K, V, S>>, Side)>;
+
+// 把 subtree 依次挂回路径上的节点并返回新的根，
+// rebalance 为 false 时只更新高度、大小与摘要
+fn retrace<K, V, S: Summary<K, V>>(
+  mut path: Path<K, V, S>,
+  mut subtree: Option<Box<AVLNode<K, V, S>>>,
+  rebalance: bool,
+) -> Option<Box<AVLNode<K, V, S>>> {
+  while let Some((mut node, side)) = path.pop() {
+    *node.child_mut(side) = subtree;
+    if rebalance {
       node.rebalance();
-      // 各个根节点重新指向平衡后的子树
-      *tree = Some(node);
-      // 逐级向上传最小节点
-      Some(small)
     } else {
-      // 用最小节点的右子树取代该节点位置，如果没有右子树，则为空
-      *tree = node.right.take();
-      // 返回该最小节点
-      Some(node)
+      node.update_height();
     }
-  } else {
-    None
+    subtree = Some(node);
   }
+  subtree
+}
+
+/// 插入键值对，键已存在时替换值并返回旧值。
+/// 使用显式路径栈代替递归，栈的长度不超过树高
+pub(crate) fn insert<K, V, S: Summary<K, V>, C: Comparator<K>>(
+  tree: &mut Option<Box<AVLNode<K, V, S>>>,
+  key: K,
+  value: V,
+  cmp: &C,
+) -> Result<(), V> {
+  let mut path: Path<K, V, S> = Vec::with_capacity(tree.as_ref().map_or(0, |n| n.height));
+  let mut subtree = tree.take();
+  let inserted = loop {
+    // 到达空位置，直接插入
+    let Some(mut node) = subtree.take() else {
+      subtree = Some(AVLNode::new(key, value));
+      break Ok(());
+    };
+    let side = match cmp.compare(&key, &node.key) {
+      Ordering::Equal => {
+        let old = mem::replace(&mut node.value, value);
+        node.update_height();
+        subtree = Some(node);
+        break Err(old);
+      }
+      Ordering::Less => Side::Left,
+      Ordering::Greater => Side::Right,
+    };
+    subtree = node.child_mut(side).take();
+    path.push((node, side));
+  };
+  // 如果插入新节点，需要重新平衡，替换旧值时只需重新计算路径上的摘要
+  *tree = retrace(path, subtree, inserted.is_ok());
+  inserted
 }
 
-pub struct Iter<'a, T: Ord> {
-  node_stack: Vec<&'a AVLNode<T>>,
+// search_mut 的结果：找到的节点，或者插入路径上最深的不平衡节点的深度(路径上都平衡时为 None)
+pub(crate) enum Search<'a, K, V, S> {
+  Found(&'a mut AVLNode<K, V, S>),
+  Vacant(Option<usize>),
 }
 
-impl<'a, T: Ord> Iterator for Iter<'a, T> {
-  type Item = &'a T;
-  fn next(&mut self) -> Option<Self::Item> {
-    if let Some(node) = self.node_stack.pop() {
-      // Push left path of right subtree to stack
-      let mut child = &node.right;
-      while let Some(subtree) = child {
-        self.node_stack.push(subtree.as_ref());
-        child = &subtree.left;
+/// 查找键对应的节点，找不到时记下 insert_vacant 需要的不平衡节点深度
+pub(crate) fn search_mut<'a, K, V, S, Q, C>(
+  tree: &'a mut Option<Box<AVLNode<K, V, S>>>,
+  key: &Q,
+  cmp: &C,
+) -> Search<'a, K, V, S>
+where
+  K: Borrow<Q>,
+  Q: ?Sized,
+  C: Comparator<Q>,
+{
+  let mut critical = None;
+  let mut depth = 0;
+  let mut child = tree;
+  while let Some(node) = child {
+    if node.balance_factor() != 0 {
+      critical = Some(depth);
+    }
+    child = match cmp.compare(key, node.key.borrow()) {
+      Ordering::Equal => return Search::Found(node),
+      Ordering::Less => &mut node.left,
+      Ordering::Greater => &mut node.right,
+    };
+    depth += 1;
+  }
+  Search::Vacant(critical)
+}
+
+/// 插入树中不存在的键并返回新值的引用，critical 为 search_mut 在同一棵树上返回的深度。
+/// critical 以下的路径节点原本都是平衡的，插入后高度各加一；critical 处的节点偏向插入一侧时
+/// 插入后会失衡，因此在下降经过它时预先旋转，插入后不需要回溯。
+/// 节点的高度与大小在下降时直接修改，所以只适用于没有摘要的树
+pub(crate) fn insert_vacant<'a, K, V, C: Comparator<K>>(
+  tree: &'a mut Option<Box<AVLNode<K, V>>>,
+  key: K,
+  value: V,
+  critical: Option<usize>,
+  cmp: &C,
+) -> &'a mut V {
+  let side_of = |node: &AVLNode<K, V>| match cmp.compare(&key, &node.key) {
+    Ordering::Less => Side::Left,
+    _ => Side::Right,
+  };
+  // 深度不小于 grow_from 的路径节点在插入后高度加一
+  let mut grow_from = critical.map_or(0, |depth| depth + 1);
+  let mut slot = tree;
+  let mut depth = 0;
+  while slot.is_some() {
+    let node = slot.as_mut().unwrap();
+    let mut side = side_of(node);
+    let heavy = match node.balance_factor() {
+      -1 => Some(Side::Left),
+      1 => Some(Side::Right),
+      _ => None,
+    };
+    if Some(depth) == critical && heavy == Some(side) {
+      let child = node.child(side).as_ref().unwrap();
+      let inner = side_of(child);
+      if inner == side {
+        // 同侧插入：单旋后原来的孩子成为子树根，偏向另一侧，插入后恰好平衡
+        node.rotate(!side);
+      } else if child.child(inner).is_none() {
+        // 异侧插入且孩子是叶子：新节点成为子树根，两侧各挂一个叶子
+        let child = node.child_mut(side).take();
+        node.update_height();
+        let mut new = AVLNode::new(key, value);
+        *new.child_mut(side) = child;
+        mem::swap(node, &mut new);
+        *node.child_mut(!side) = Some(new);
+        node.update_height();
+        return &mut node.value;
+      } else {
+        // 异侧插入：双旋后孙子成为子树根，它的两个孩子各偏向外侧，插入后不改变它们的高度
+        node.child_mut(side).as_mut().unwrap().rotate(side);
+        node.rotate(!side);
+        grow_from = depth + 2;
       }
-      Some(&node.val)
-    } else {
-      None
+      side = side_of(node);
+    }
+    node.size += 1;
+    if depth >= grow_from {
+      node.height += 1;
     }
+    slot = node.child_mut(side);
+    depth += 1;
   }
+  &mut slot.insert(AVLNode::new(key, value)).value
+}
+
+/// 修改键对应的值，并重新计算从该节点到根路径上的摘要
+pub(crate) fn update<K, V, S: Summary<K, V>, Q, C, R>(
+  tree: &mut Option<Box<AVLNode<K, V, S>>>,
+  key: &Q,
+  cmp: &C,
+  f: impl FnOnce(&mut V) -> R,
+) -> Option<R>
+where
+  K: Borrow<Q>,
+  Q: ?Sized,
+  C: Comparator<Q>,
+{
+  let node = tree.as_mut()?;
+  let result = match cmp.compare(key, node.key.borrow()) {
+    Ordering::Less => update(&mut node.left, key, cmp, f),
+    Ordering::Greater => update(&mut node.right, key, cmp, f),
+    Ordering::Equal => Some(f(&mut node.value)),
+  };
+  if result.is_some() {
+    node.update_height();
+  }
+  result
+}
+
+/// 合并键位于 [start, end] 范围内的节点摘要，完整落在范围内的子树直接使用其摘要，
+/// 因此只需沿两条边界路径访问 O(log n) 个节点
+pub(crate) fn fold<K, V, S: Summary<K, V>, Q, C>(
+  tree: &Option<Box<AVLNode<K, V, S>>>,
+  start: Bound<&Q>,
+  end: Bound<&Q>,
+  cmp: &C,
+) -> S
+where
+  K: Borrow<Q>,
+  Q: ?Sized,
+  C: Comparator<Q>,
+{
+  let Some(node) = tree else {
+    return S::empty();
+  };
+  if let (Bound::Unbounded, Bound::Unbounded) = (start, end) {
+    return node.summary.clone();
+  }
+  let below_start = match start {
+    Bound::Included(start) => cmp.compare(node.key.borrow(), start).is_lt(),
+    Bound::Excluded(start) => cmp.compare(node.key.borrow(), start).is_le(),
+    Bound::Unbounded => false,
+  };
+  let above_end = match end {
+    Bound::Included(end) => cmp.compare(node.key.borrow(), end).is_gt(),
+    Bound::Excluded(end) => cmp.compare(node.key.borrow(), end).is_ge(),
+    Bound::Unbounded => false,
+  };
+  match (below_start, above_end) {
+    (true, _) => fold(&node.right, start, end, cmp),
+    (_, true) => fold(&node.left, start, end, cmp),
+    // 当前节点在范围内，左子树只受下界限制，右子树只受上界限制
+    _ => {
+      let left = fold(&node.left, start, Bound::Unbounded, cmp);
+      let right = fold(&node.right, Bound::Unbounded, end, cmp);
+      S::combine(
+        &S::combine(&left, &S::single(&node.key, &node.value)),
+        &right,
+      )
+    }
+  }
+}
+
+/// 删除键对应的节点，返回被删除的键值对
+pub(crate) fn remove<K, V, S: Summary<K, V>, Q, C>(
+  tree: &mut Option<Box<AVLNode<K, V, S>>>,
+  key: &Q,
+  cmp: &C,
+) -> Option<(K, V)>
+where
+  K: Borrow<Q>,
+  Q: ?Sized,
+  C: Comparator<Q>,
+{
+  let mut path: Path<K, V, S> = Vec::with_capacity(tree.as_ref().map_or(0, |n| n.height));
+  let mut subtree = tree.take();
+  let removed = loop {
+    let Some(mut node) = subtree.take() else {
+      break None;
+    };
+    let side = match cmp.compare(key, node.key.borrow()) {
+      Ordering::Less => Side::Left,
+      Ordering::Greater => Side::Right,
+      Ordering::Equal => {
+        subtree = unlink(&mut node);
+        break Some((node.key, node.value));
+      }
+    };
+    subtree = node.child_mut(side).take();
+    path.push((node, side));
+  };
+  *tree = retrace(path, subtree, removed.is_some());
+  removed
+}
+
+/// 删除中序遍历第 k 个节点(从 0 开始)，返回被删除的键值对
+pub(crate) fn remove_nth<K, V, S: Summary<K, V>>(
+  tree: &mut Option<Box<AVLNode<K, V, S>>>,
+  k: usize,
+) -> Option<(K, V)> {
+  let node = tree.as_mut()?;
+  let left_size = node.size(Side::Left);
+  let removed = match k.cmp(&left_size) {
+    Ordering::Less => remove_nth(&mut node.left, k),
+    Ordering::Greater => remove_nth(&mut node.right, k - left_size - 1),
+    Ordering::Equal => {
+      let mut node = tree.take().unwrap();
+      *tree = unlink(&mut node);
+      return Some((node.key, node.value));
+    }
+  };
+  if removed.is_some() {
+    node.rebalance();
+  }
+  removed
+}
+
+/// 查找键对应的节点
+pub(crate) fn get<'a, K, V, S, Q, C>(
+  tree: &'a Option<Box<AVLNode<K, V, S>>>,
+  key: &Q,
+  cmp: &C,
+) -> Option<&'a AVLNode<K, V, S>>
+where
+  K: Borrow<Q>,
+  Q: ?Sized,
+  C: Comparator<Q>,
+{
+  let mut child = tree;
+  while let Some(node) = child {
+    child = match cmp.compare(key, node.key.borrow()) {
+      Ordering::Equal => return Some(node),
+      Ordering::Less => &node.left,
+      Ordering::Greater => &node.right,
+    };
+  }
+  None
+}
+
+/// 查找键对应的节点，返回可变引用
+pub(crate) fn get_mut<'a, K, V, S, Q, C>(
+  tree: &'a mut Option<Box<AVLNode<K, V, S>>>,
+  key: &Q,
+  cmp: &C,
+) -> Option<&'a mut AVLNode<K, V, S>>
+where
+  K: Borrow<Q>,
+  Q: ?Sized,
+  C: Comparator<Q>,
+{
+  let mut child = tree;
+  while let Some(node) = child {
+    child = match cmp.compare(key, node.key.borrow()) {
+      Ordering::Equal => return Some(node),
+      Ordering::Less => &mut node.left,
+      Ordering::Greater => &mut node.right,
+    };
+  }
+  None
+}
+
+/// 统计小于 key 的节点数，inclusive 为 true 时统计小于等于 key 的节点数
+pub(crate) fn rank<K, V, S, Q, C>(
+  tree: &Option<Box<AVLNode<K, V, S>>>,
+  key: &Q,
+  inclusive: bool,
+  cmp: &C,
+) -> usize
+where
+  K: Borrow<Q>,
+  Q: ?Sized,
+  C: Comparator<Q>,
+{
+  let mut count = 0;
+  let mut child = tree;
+  while let Some(node) = child {
+    child = match cmp.compare(key, node.key.borrow()) {
+      Ordering::Less => &node.left,
+      Ordering::Equal => return count + node.size(Side::Left) + inclusive as usize,
+      Ordering::Greater => {
+        count += node.size(Side::Left) + 1;
+        &node.right
+      }
+    };
+  }
+  count
+}
+
+/// 查找中序遍历第 k 个节点(从 0 开始)
+pub(crate) fn select<K, V, S>(
+  tree: &Option<Box<AVLNode<K, V, S>>>,
+  mut k: usize,
+) -> Option<&AVLNode<K, V, S>> {
+  let mut child = tree;
+  while let Some(node) = child {
+    let left_size = node.size(Side::Left);
+    child = match k.cmp(&left_size) {
+      Ordering::Less => &node.left,
+      Ordering::Equal => return Some(node),
+      Ordering::Greater => {
+        k -= left_size + 1;
+        &node.right
+      }
+    };
+  }
+  None
+}
+
+/// 摘除节点，返回由其左右子树组成的新子树
+fn unlink<K, V, S: Summary<K, V>>(node: &mut AVLNode<K, V, S>) -> Option<Box<AVLNode<K, V, S>>> {
+  match (node.left.take(), node.right.take()) {
+    // 叶子节点直接删除
+    (None, None) => None,
+    // 只有一个子树，直接替换
+    (Some(left), None) => Some(left),
+    (None, Some(right)) => Some(right),
+    // 有两个子树，找到右子树最小节点替换
+    (Some(left), Some(right)) => Some(merge(left, right)),
+  }
+}
+
+/// 合并两个子树
+fn merge<K, V, S: Summary<K, V>>(
+  left: Box<AVLNode<K, V, S>>,
+  right: Box<AVLNode<K, V, S>>,
+) -> Box<AVLNode<K, V, S>> {
+  let mut op_right = Some(right);
+  // 合并后的子树根节点为右子树最小节点
+  let mut root = take_min(&mut op_right).unwrap();
+  // 左子树为原左子树，右子树为删除最小节点后平衡的右子树
+  root.left = Some(left);
+  root.right = op_right;
+  // 重新平衡
+  root.rebalance();
+  root
+}
+
+/// 以 mid 为根连接两棵子树，要求 left 中的键都小于 mid，right 中的键都大于 mid，
+/// 沿较高子树的边界下降到高度相近处再连接，回溯时重新平衡，复杂度为两树高度差
+fn join<K, V, S: Summary<K, V>>(
+  left: Option<Box<AVLNode<K, V, S>>>,
+  mut mid: Box<AVLNode<K, V, S>>,
+  right: Option<Box<AVLNode<K, V, S>>>,
+) -> Box<AVLNode<K, V, S>> {
+  let left_height = left.as_ref().map_or(0, |n| n.height);
+  let right_height = right.as_ref().map_or(0, |n| n.height);
+  if left_height > right_height + 1 {
+    let mut left = left.unwrap();
+    let inner = left.right.take();
+    left.right = Some(join(inner, mid, right));
+    left.rebalance();
+    left
+  } else if right_height > left_height + 1 {
+    let mut right = right.unwrap();
+    let inner = right.left.take();
+    right.left = Some(join(left, mid, inner));
+    right.rebalance();
+    right
+  } else {
+    mid.left = left;
+    mid.right = right;
+    mid.update_height();
+    mid
+  }
+}
+
+/// 连接两棵子树，要求 left 中的键都小于 right 中的键
+fn join2<K, V, S: Summary<K, V>>(
+  left: Option<Box<AVLNode<K, V, S>>>,
+  mut right: Option<Box<AVLNode<K, V, S>>>,
+) -> Option<Box<AVLNode<K, V, S>>> {
+  match take_min(&mut right) {
+    Some(mid) => Some(join(left, mid, right)),
+    None => left,
+  }
+}
+
+type Split<K, V, S> = (
+  Option<Box<AVLNode<K, V, S>>>,
+  Option<Box<AVLNode<K, V, S>>>,
+  Option<Box<AVLNode<K, V, S>>>,
+);
+
+/// 按 key 将树拆分为小于 key 的子树、与 key 相等的节点和大于 key 的子树
+fn split<K, V, S: Summary<K, V>, Q, C>(
+  tree: Option<Box<AVLNode<K, V, S>>>,
+  key: &Q,
+  cmp: &C,
+) -> Split<K, V, S>
+where
+  K: Borrow<Q>,
+  Q: ?Sized,
+  C: Comparator<Q>,
+{
+  let Some(mut node) = tree else {
+    return (None, None, None);
+  };
+  let (left, right) = (node.left.take(), node.right.take());
+  match cmp.compare(key, node.key.borrow()) {
+    Ordering::Equal => (left, Some(node), right),
+    Ordering::Less => {
+      let (less, found, greater) = split(left, key, cmp);
+      (less, found, Some(join(greater, node, right)))
+    }
+    Ordering::Greater => {
+      let (less, found, greater) = split(right, key, cmp);
+      (Some(join(left, node, less)), found, greater)
+    }
+  }
+}
+
+/// 并集，键相同时保留 a 中的节点
+fn union<K, V, S: Summary<K, V>, C: Comparator<K>>(
+  a: Option<Box<AVLNode<K, V, S>>>,
+  b: Option<Box<AVLNode<K, V, S>>>,
+  cmp: &C,
+) -> Option<Box<AVLNode<K, V, S>>> {
+  let Some(mut node) = a else {
+    return b;
+  };
+  let (left, right) = (node.left.take(), node.right.take());
+  let (less, _, greater) = split(b, &node.key, cmp);
+  Some(join(
+    union(left, less, cmp),
+    node,
+    union(right, greater, cmp),
+  ))
+}
+
+/// 交集，保留 a 中的节点
+fn intersection<K, V, S: Summary<K, V>, C: Comparator<K>>(
+  a: Option<Box<AVLNode<K, V, S>>>,
+  b: Option<Box<AVLNode<K, V, S>>>,
+  cmp: &C,
+) -> Option<Box<AVLNode<K, V, S>>> {
+  let mut node = a?;
+  let (left, right) = (node.left.take(), node.right.take());
+  let (less, found, greater) = split(b, &node.key, cmp);
+  let (left, right) = (
+    intersection(left, less, cmp),
+    intersection(right, greater, cmp),
+  );
+  match found {
+    Some(_) => Some(join(left, node, right)),
+    None => join2(left, right),
+  }
+}
+
+/// 差集，保留 a 中不在 b 中的节点
+fn difference<K, V, S: Summary<K, V>, C: Comparator<K>>(
+  a: Option<Box<AVLNode<K, V, S>>>,
+  b: Option<Box<AVLNode<K, V, S>>>,
+  cmp: &C,
+) -> Option<Box<AVLNode<K, V, S>>> {
+  let mut node = a?;
+  let (left, right) = (node.left.take(), node.right.take());
+  let (less, found, greater) = split(b, &node.key, cmp);
+  let (left, right) = (difference(left, less, cmp), difference(right, greater, cmp));
+  match found {
+    Some(_) => join2(left, right),
+    None => Some(join(left, node, right)),
+  }
+}
+
+/// 取出最小节点
+fn take_min<K, V, S: Summary<K, V>>(
+  tree: &mut Option<Box<AVLNode<K, V, S>>>,
+) -> Option<Box<AVLNode<K, V, S>>> {
+  take_extreme(tree, Side::Left)
+}
+
+/// 取出最大节点
+fn take_max<K, V, S: Summary<K, V>>(
+  tree: &mut Option<Box<AVLNode<K, V, S>>>,
+) -> Option<Box<AVLNode<K, V, S>>> {
+  take_extreme(tree, Side::Right)
+}
+
+/// 取出 side 方向上最末端的节点，用它另一侧的子树取代其位置，再沿路径向上重新平衡
+fn take_extreme<K, V, S: Summary<K, V>>(
+  tree: &mut Option<Box<AVLNode<K, V, S>>>,
+  side: Side,
+) -> Option<Box<AVLNode<K, V, S>>> {
+  let mut path: Path<K, V, S> = Vec::with_capacity(tree.as_ref().map_or(0, |n| n.height));
+  let mut subtree = tree.take();
+  let taken = loop {
+    let Some(mut node) = subtree.take() else {
+      break None;
+    };
+    match node.child_mut(side).take() {
+      Some(child) => {
+        subtree = Some(child);
+        path.push((node, side));
+      }
+      None => {
+        subtree = node.child_mut(!side).take();
+        break Some(node);
+      }
+    }
+  };
+  *tree = retrace(path, subtree, true);
+  taken
+}
+
+/// 查找 side 方向上最末端的节点，Left 为最小节点，Right 为最大节点
+fn extreme<K, V, S>(tree: &Option<Box<AVLNode<K, V, S>>>, side: Side) -> Option<&AVLNode<K, V, S>> {
+  let mut node = tree.as_deref()?;
+  while let Some(child) = node.child(side) {
+    node = child;
+  }
+  Some(node)
+}
+
+/// 查找 key 在 side 方向上最近的节点，Left 为小于 key 的最大节点，Right 为大于 key 的最小节点，
+/// inclusive 为 true 时与 key 相等的节点也满足条件
+fn nearest<'a, K, V, S, Q, C>(
+  tree: &'a Option<Box<AVLNode<K, V, S>>>,
+  key: &Q,
+  side: Side,
+  inclusive: bool,
+  cmp: &C,
+) -> Option<&'a AVLNode<K, V, S>>
+where
+  K: Borrow<Q>,
+  Q: ?Sized,
+  C: Comparator<Q>,
+{
+  let mut found = None;
+  let mut child = tree;
+  while let Some(node) = child {
+    child = match (cmp.compare(key, node.key.borrow()), side) {
+      (Ordering::Equal, _) if inclusive => return Some(node),
+      // 节点位于 key 的 side 一侧，记录为候选并向 key 靠近
+      (Ordering::Greater, Side::Left) | (Ordering::Less, Side::Right) => {
+        found = Some(node.as_ref());
+        node.child(!side)
+      }
+      _ => node.child(side),
+    };
+  }
+  found
+}
+
+// 双端中序遍历，front 与 back 分别保存两端尚未访问的祖先路径，remaining 防止两端交错
+struct RawIter<'a, K, V> {
+  front: Vec<&'a AVLNode<K, V>>,
+  back: Vec<&'a AVLNode<K, V>>,
+  remaining: usize,
+}
+
+impl<'a, K, V> Clone for RawIter<'a, K, V> {
+  fn clone(&self) -> Self {
+    Self {
+      front: self.front.clone(),
+      back: self.back.clone(),
+      remaining: self.remaining,
+    }
+  }
+}
+
+impl<'a, K, V> RawIter<'a, K, V> {
+  fn new<Q, C>(
+    tree: &'a Option<Box<AVLNode<K, V>>>,
+    start: Bound<&Q>,
+    end: Bound<&Q>,
+    remaining: usize,
+    cmp: &C,
+  ) -> Self
+  where
+    K: Borrow<Q>,
+    Q: ?Sized,
+    C: Comparator<Q>,
+  {
+    let cap = tree.as_ref().map_or(0, |n| n.height);
+    let mut iter = Self {
+      front: Vec::with_capacity(cap),
+      back: Vec::with_capacity(cap),
+      remaining,
+    };
+    // 从根向下寻找下界，满足下界的节点入栈后继续向左
+    let mut child = tree;
+    while let Some(node) = child {
+      let above_start = match start {
+        Bound::Included(start) => cmp.compare(node.key.borrow(), start).is_ge(),
+        Bound::Excluded(start) => cmp.compare(node.key.borrow(), start).is_gt(),
+        Bound::Unbounded => true,
+      };
+      if above_start {
+        iter.front.push(node);
+        child = &node.left;
+      } else {
+        child = &node.right;
+      }
+    }
+    // 从根向下寻找上界，满足上界的节点入栈后继续向右
+    let mut child = tree;
+    while let Some(node) = child {
+      let below_end = match end {
+        Bound::Included(end) => cmp.compare(node.key.borrow(), end).is_le(),
+        Bound::Excluded(end) => cmp.compare(node.key.borrow(), end).is_lt(),
+        Bound::Unbounded => true,
+      };
+      if below_end {
+        iter.back.push(node);
+        child = &node.right;
+      } else {
+        child = &node.left;
+      }
+    }
+    iter
+  }
+
+  fn next(&mut self) -> Option<&'a AVLNode<K, V>> {
+    if self.remaining == 0 {
+      return None;
+    }
+    let node = self.front.pop()?;
+    // 将右子树的左侧路径入栈
+    let mut child = &node.right;
+    while let Some(subtree) = child {
+      self.front.push(subtree);
+      child = &subtree.left;
+    }
+    self.remaining -= 1;
+    Some(node)
+  }
+
+  fn next_back(&mut self) -> Option<&'a AVLNode<K, V>> {
+    if self.remaining == 0 {
+      return None;
+    }
+    let node = self.back.pop()?;
+    // 将左子树的右侧路径入栈
+    let mut child = &node.left;
+    while let Some(subtree) = child {
+      self.back.push(subtree);
+      child = &subtree.right;
+    }
+    self.remaining -= 1;
+    Some(node)
+  }
+}
+
+pub struct Iter<'a, T> {
+  raw: RawIter<'a, T, ()>,
+}
+
+impl<'a, T> Clone for Iter<'a, T> {
+  fn clone(&self) -> Self {
+    Self {
+      raw: self.raw.clone(),
+    }
+  }
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+  type Item = &'a T;
+  fn next(&mut self) -> Option<Self::Item> {
+    self.raw.next().map(|node| &node.key)
+  }
+
+  fn size_hint(&self) -> (usize, Option<usize>) {
+    (self.raw.remaining, Some(self.raw.remaining))
+  }
+}
+
+impl<'a, T> DoubleEndedIterator for Iter<'a, T> {
+  fn next_back(&mut self) -> Option<Self::Item> {
+    self.raw.next_back().map(|node| &node.key)
+  }
+}
+
+impl<'a, T> ExactSizeIterator for Iter<'a, T> {}
+
+impl<'a, T> FusedIterator for Iter<'a, T> {}
+
+pub struct Range<'a, T> {
+  raw: RawIter<'a, T, ()>,
+}
+
+impl<'a, T> Clone for Range<'a, T> {
+  fn clone(&self) -> Self {
+    Self {
+      raw: self.raw.clone(),
+    }
+  }
+}
+
+impl<'a, T> Iterator for Range<'a, T> {
+  type Item = &'a T;
+  fn next(&mut self) -> Option<Self::Item> {
+    self.raw.next().map(|node| &node.key)
+  }
+
+  fn size_hint(&self) -> (usize, Option<usize>) {
+    (self.raw.remaining, Some(self.raw.remaining))
+  }
+}
+
+impl<'a, T> DoubleEndedIterator for Range<'a, T> {
+  fn next_back(&mut self) -> Option<Self::Item> {
+    self.raw.next_back().map(|node| &node.key)
+  }
+}
+
+impl<'a, T> ExactSizeIterator for Range<'a, T> {}
+
+impl<'a, T> FusedIterator for Range<'a, T> {}
+
+// 按值遍历，构造时一次性将树按中序展开
+pub struct IntoIter<T> {
+  inner: vec::IntoIter<(T, ())>,
+}
+
+impl<T> Iterator for IntoIter<T> {
+  type Item = T;
+  fn next(&mut self) -> Option<Self::Item> {
+    self.inner.next().map(|(key, _)| key)
+  }
+
+  fn size_hint(&self) -> (usize, Option<usize>) {
+    self.inner.size_hint()
+  }
+}
+
+impl<T> DoubleEndedIterator for IntoIter<T> {
+  fn next_back(&mut self) -> Option<Self::Item> {
+    self.inner.next_back().map(|(key, _)| key)
+  }
+}
+
+impl<T> ExactSizeIterator for IntoIter<T> {}
+
+impl<T> FusedIterator for IntoIter<T> {}
+
+impl<T, C> IntoIterator for AVLTree<T, C> {
+  type Item = T;
+  type IntoIter = IntoIter<T>;
+  fn into_iter(self) -> Self::IntoIter {
+    let mut out = Vec::with_capacity(self.length);
+    flatten(self.root, &mut out);
+    IntoIter {
+      inner: out.into_iter(),
+    }
+  }
+}
+
+impl<'a, T, C: Comparator<T>> IntoIterator for &'a AVLTree<T, C> {
+  type Item = &'a T;
+  type IntoIter = Iter<'a, T>;
+  fn into_iter(self) -> Self::IntoIter {
+    self.iter()
+  }
+}
+
+// 先排序去重再线性构造，重复元素保留最先出现的一个，与逐个 insert 的结果一致
+impl<T, C: Comparator<T> + Default> FromIterator<T> for AVLTree<T, C> {
+  fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+    let cmp = C::default();
+    let mut vals: Vec<(T, ())> = iter.into_iter().map(|val| (val, ())).collect();
+    vals.sort_by(|a, b| cmp.compare(&a.0, &b.0));
+    vals.dedup_by(|a, b| cmp.compare(&a.0, &b.0).is_eq());
+    Self::from_sorted_vec(vals, cmp)
+  }
+}
+
+impl<T, C: Comparator<T>> Extend<T> for AVLTree<T, C> {
+  fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+    for val in iter {
+      self.insert(val);
+    }
+  }
+}
+
+impl<'a, T: Copy + 'a, C: Comparator<T>> Extend<&'a T> for AVLTree<T, C> {
+  fn extend<I: IntoIterator<Item = &'a T>>(&mut self, iter: I) {
+    self.extend(iter.into_iter().copied());
+  }
+}
+
+impl<T: PartialEq, C: Comparator<T>> PartialEq for AVLTree<T, C> {
+  fn eq(&self, other: &Self) -> bool {
+    self.length == other.length && self.iter().eq(other.iter())
+  }
+}
+
+impl<T: Eq, C: Comparator<T>> Eq for AVLTree<T, C> {}
+
+impl<T: Ord, C: Comparator<T>> PartialOrd for AVLTree<T, C> {
+  fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+    Some(self.cmp(other))
+  }
+}
+
+// 按元素序列的字典序比较
+impl<T: Ord, C: Comparator<T>> Ord for AVLTree<T, C> {
+  fn cmp(&self, other: &Self) -> Ordering {
+    self.iter().cmp(other.iter())
+  }
+}
+
+impl<T: Hash, C: Comparator<T>> Hash for AVLTree<T, C> {
+  fn hash<H: Hasher>(&self, state: &mut H) {
+    state.write_usize(self.length);
+    for val in self {
+      val.hash(state);
+    }
+  }
+}
+
+impl<T: Debug, C: Comparator<T>> Debug for AVLTree<T, C> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_set().entries(self.iter()).finish()
+  }
+}
+
+// 序列化为按比较器顺序排列的序列
+#[cfg(feature = "serde")]
+impl<T: serde::Serialize, C: Comparator<T>> serde::Serialize for AVLTree<T, C> {
+  fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_seq(self.iter())
+  }
+}
+
+// 反序列化时要求序列有序，并以线性时间批量构建，乱序的输入返回错误
+#[cfg(feature = "serde")]
+impl<'de, T, C> serde::Deserialize<'de> for AVLTree<T, C>
+where
+  T: serde::Deserialize<'de>,
+  C: Comparator<T> + Default,
+{
+  fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+    let vals = Vec::<T>::deserialize(deserializer)?;
+    let cmp = C::default();
+    // 与 from_snapshot 一致，元素须严格递增，重复元素也视为乱序
+    if let Some(index) = vals
+      .windows(2)
+      .position(|pair| cmp.compare(&pair[0], &pair[1]).is_ge())
+    {
+      return Err(serde::de::Error::custom(OutOfOrder { index: index + 1 }));
+    }
+    Ok(Self::from_sorted_vec(
+      vals.into_iter().map(|val| (val, ())).collect(),
+      cmp,
+    ))
+  }
+}
+
+// 游标按排名定位元素，index 等于元素个数时指向首尾之间的空位置，
+// 在空位置上向后移动回到第一个元素，向前移动回到最后一个元素
+pub struct Cursor<'a, T, C = Natural> {
+  tree: &'a AVLTree<T, C>,
+  index: usize,
+}
+
+impl<'a, T, C> Clone for Cursor<'a, T, C> {
+  fn clone(&self) -> Self {
+    *self
+  }
+}
+
+impl<'a, T, C> Copy for Cursor<'a, T, C> {}
+
+impl<'a, T, C: Comparator<T>> Cursor<'a, T, C> {
+  // 当前元素的排名，位于空位置时返回 None
+  pub fn index(&self) -> Option<usize> {
+    (self.index < self.tree.length).then_some(self.index)
+  }
+
+  pub fn current(&self) -> Option<&'a T> {
+    self.tree.select(self.index)
+  }
+
+  pub fn peek_next(&self) -> Option<&'a T> {
+    self.tree.select(next_index(self.index, self.tree.length))
+  }
+
+  pub fn peek_prev(&self) -> Option<&'a T> {
+    self.tree.select(prev_index(self.index, self.tree.length))
+  }
+
+  pub fn move_next(&mut self) {
+    self.index = next_index(self.index, self.tree.length);
+  }
+
+  pub fn move_prev(&mut self) {
+    self.index = prev_index(self.index, self.tree.length);
+  }
+}
+
+pub struct CursorMut<'a, T, C = Natural> {
+  tree: &'a mut AVLTree<T, C>,
+  index: usize,
+}
+
+impl<'a, T, C: Comparator<T>> CursorMut<'a, T, C> {
+  pub fn index(&self) -> Option<usize> {
+    self.as_cursor().index()
+  }
+
+  pub fn current(&self) -> Option<&T> {
+    self.tree.select(self.index)
+  }
+
+  pub fn peek_next(&self) -> Option<&T> {
+    self.tree.select(next_index(self.index, self.tree.length))
+  }
+
+  pub fn peek_prev(&self) -> Option<&T> {
+    self.tree.select(prev_index(self.index, self.tree.length))
+  }
+
+  pub fn move_next(&mut self) {
+    self.index = next_index(self.index, self.tree.length);
+  }
+
+  pub fn move_prev(&mut self) {
+    self.index = prev_index(self.index, self.tree.length);
+  }
+
+  pub fn as_cursor(&self) -> Cursor<'_, T, C> {
+    Cursor {
+      tree: self.tree,
+      index: self.index,
+    }
+  }
+
+  // 删除当前元素，游标随后指向原来的下一个元素
+  pub fn remove_current(&mut self) -> Option<T> {
+    let removed = remove_nth(&mut self.tree.root, self.index).map(|(key, _)| key);
+    if removed.is_some() {
+      self.tree.length -= 1;
+    }
+    removed
+  }
+
+  // 插入元素，游标仍指向原来的元素
+  pub fn insert(&mut self, val: T) -> bool {
+    let at = rank(&self.tree.root, &val, false, &self.tree.cmp);
+    let inserted = self.tree.insert(val);
+    if inserted && at <= self.index {
+      self.index += 1;
+    }
+    inserted
+  }
+}
+
+// 游标在 length + 1 个位置(含空位置)上循环移动
+fn next_index(index: usize, length: usize) -> usize {
+  (index + 1) % (length + 1)
+}
+
+fn prev_index(index: usize, length: usize) -> usize {
+  (index + length) % (length + 1)
+}
+
+// from_sorted_iter 与 BTree::bulk_load 遇到降序元素时返回的错误
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OutOfOrder {
+  pub index: usize,
+}
+
+impl Display for OutOfOrder {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "element at index {} is out of order", self.index)
+  }
+}
+
+impl Error for OutOfOrder {}
+
+// validate 发现的不变式破坏，节点以其中序位置 index 标识
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AVLViolation {
+  // 第 index 个元素不大于前一个元素
+  Unordered {
+    index: usize,
+  },
+  // 记录的高度与实际高度不一致
+  HeightMismatch {
+    index: usize,
+    stored: usize,
+    actual: usize,
+  },
+  // 左右子树高度差超过 1
+  Unbalanced {
+    index: usize,
+    balance_factor: isize,
+  },
+  // 记录的子树大小与实际节点数不一致
+  SizeMismatch {
+    index: usize,
+    stored: usize,
+    actual: usize,
+  },
+  // length 与树中的节点数不一致
+  LengthMismatch {
+    length: usize,
+    actual: usize,
+  },
+}
+
+impl Display for AVLViolation {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      AVLViolation::Unordered { index } => {
+        write!(f, "element {} is not greater than its predecessor", index)
+      }
+      AVLViolation::HeightMismatch {
+        index,
+        stored,
+        actual,
+      } => write!(
+        f,
+        "node {} stores height {} but has height {}",
+        index, stored, actual
+      ),
+      AVLViolation::Unbalanced {
+        index,
+        balance_factor,
+      } => write!(f, "node {} has balance factor {}", index, balance_factor),
+      AVLViolation::SizeMismatch {
+        index,
+        stored,
+        actual,
+      } => write!(
+        f,
+        "node {} stores size {} but has {} nodes",
+        index, stored, actual
+      ),
+      AVLViolation::LengthMismatch { length, actual } => {
+        write!(f, "tree length is {} but has {} nodes", length, actual)
+      }
+    }
+  }
+}
+
+impl Error for AVLViolation {}
+
+/// 后序检查子树的排序、高度、平衡因子与子树大小，返回子树的实际高度与节点数，
+/// prev 为中序遍历的前一个键，index 为当前节点的中序位置
+pub(crate) fn validate<'a, K, V, S, C: Comparator<K>>(
+  tree: &'a Option<Box<AVLNode<K, V, S>>>,
+  cmp: &C,
+  prev: &mut Option<&'a K>,
+  index: &mut usize,
+) -> Result<(usize, usize), AVLViolation> {
+  let Some(node) = tree else {
+    return Ok((0, 0));
+  };
+  let (left_height, left_size) = validate(&node.left, cmp, prev, index)?;
+  let at = *index;
+  if let Some(prev) = prev {
+    if cmp.compare(prev, &node.key).is_ge() {
+      return Err(AVLViolation::Unordered { index: at });
+    }
+  }
+  *prev = Some(&node.key);
+  *index += 1;
+  let (right_height, right_size) = validate(&node.right, cmp, prev, index)?;
+
+  let height = 1 + max(left_height, right_height);
+  if node.height != height {
+    return Err(AVLViolation::HeightMismatch {
+      index: at,
+      stored: node.height,
+      actual: height,
+    });
+  }
+  let balance_factor = right_height as isize - left_height as isize;
+  if balance_factor.abs() > 1 {
+    return Err(AVLViolation::Unbalanced {
+      index: at,
+      balance_factor,
+    });
+  }
+  let size = 1 + left_size + right_size;
+  if node.size != size {
+    return Err(AVLViolation::SizeMismatch {
+      index: at,
+      stored: node.size,
+      actual: size,
+    });
+  }
+  Ok((height, size))
+}
+
+// 以目录树的形式逐行输出节点，L/R 标明所在子树
+fn pretty_node<K: Debug, V>(
+  node: &AVLNode<K, V>,
+  label: &str,
+  prefix: &str,
+  child_prefix: &str,
+  out: &mut String,
+) {
+  out.push_str(&format!(
+    "{}{}{:?} (h={}, n={})\n",
+    prefix, label, node.key, node.height, node.size
+  ));
+  let children: Vec<_> = [("L: ", &node.left), ("R: ", &node.right)]
+    .into_iter()
+    .filter_map(|(label, child)| child.as_deref().map(|child| (label, child)))
+    .collect();
+  for (i, (label, child)) in children.iter().enumerate() {
+    let (branch, indent) = if i + 1 == children.len() {
+      ("└── ", "    ")
+    } else {
+      ("├── ", "│   ")
+    };
+    pretty_node(
+      child,
+      label,
+      &format!("{}{}", child_prefix, branch),
+      &format!("{}{}", child_prefix, indent),
+      out,
+    );
+  }
+}
+
+// 输出 DOT 节点与边，节点编号为先序遍历序号
+fn dot_node<K: Debug, V>(node: &AVLNode<K, V>, next_id: &mut usize, out: &mut String) -> usize {
+  let id = *next_id;
+  *next_id += 1;
+  let key = format!("{:?}", node.key)
+    .replace('\\', "\\\\")
+    .replace('"', "\\\"");
+  out.push_str(&format!(
+    "  n{} [label=\"{}\\nh={} n={}\"];\n",
+    id, key, node.height, node.size
+  ));
+  for (label, child) in [("L", &node.left), ("R", &node.right)] {
+    if let Some(child) = child {
+      let child_id = dot_node(child, next_id, out);
+      out.push_str(&format!(
+        "  n{} -> n{} [label=\"{}\"];\n",
+        id, child_id, label
+      ));
+    }
+  }
+  id
 }
 
 #[cfg(test)]
 mod tests {
-  use super::AVLTree;
+  use super::{
+    flatten, insert, insert_vacant, remove, search_mut, take_min, unlink, AVLNode, AVLTree,
+    AVLViolation, ByKey, Natural, OutOfOrder, Reversed, Search,
+  };
+  use std::{cmp::Ordering, mem, ops::Bound, time::Instant};
+
+  type Tree = Option<Box<AVLNode<i32, ()>>>;
+
+  // 递归版本的 insert、remove 与 take_min，作为迭代版本的参照与性能基准
+  fn insert_recursive(tree: &mut Tree, key: i32) -> bool {
+    let Some(node) = tree else {
+      *tree = Some(AVLNode::new(key, ()));
+      return true;
+    };
+    let inserted = match key.cmp(&node.key) {
+      Ordering::Equal => false,
+      Ordering::Less => insert_recursive(&mut node.left, key),
+      Ordering::Greater => insert_recursive(&mut node.right, key),
+    };
+    if inserted {
+      node.rebalance();
+    }
+    inserted
+  }
+
+  fn remove_recursive(tree: &mut Tree, key: i32) -> bool {
+    let Some(node) = tree else {
+      return false;
+    };
+    let removed = match key.cmp(&node.key) {
+      Ordering::Less => remove_recursive(&mut node.left, key),
+      Ordering::Greater => remove_recursive(&mut node.right, key),
+      Ordering::Equal => {
+        let mut node = tree.take().unwrap();
+        // 右子树最小节点取代被删除的节点
+        *tree = match (node.left.take(), take_min_recursive(&mut node.right)) {
+          (left, Some(mut min)) => {
+            min.left = left;
+            min.right = node.right.take();
+            min.rebalance();
+            Some(min)
+          }
+          (left, None) => left,
+        };
+        return true;
+      }
+    };
+    if removed {
+      node.rebalance();
+    }
+    removed
+  }
+
+  fn take_min_recursive(tree: &mut Tree) -> Tree {
+    let mut node = tree.take()?;
+    if let Some(small) = take_min_recursive(&mut node.left) {
+      node.rebalance();
+      *tree = Some(node);
+      Some(small)
+    } else {
+      *tree = node.right.take();
+      Some(node)
+    }
+  }
+
+  // 先序遍历的键与高度，用于比较两棵树的形状
+  fn shape(tree: &Tree, out: &mut Vec<(i32, usize)>) {
+    if let Some(node) = tree {
+      out.push((node.key, node.height));
+      shape(&node.left, out);
+      shape(&node.right, out);
+    }
+  }
+
+  fn keys(n: i32) -> impl Iterator<Item = i32> {
+    (0..n).map(move |i| (i * 7919) % n)
+  }
 
   #[test]
   fn test_avl_tree() {
@@ -260,26 +2040,463 @@ mod tests {
       avl_tree.insert(val);
     }
     let root = avl_tree.root.as_ref().unwrap();
-    assert_eq!(root.val, 32);
+    assert_eq!(root.key, 32);
     let test_other = vec![23, 31, 30];
     for val in test_other {
       avl_tree.insert(val);
     }
     let root = avl_tree.root.as_ref().unwrap();
-    assert_eq!(root.val, 22);
+    assert_eq!(root.key, 22);
     let right = root.right.as_ref().unwrap();
-    assert_eq!(right.val, 32);
+    assert_eq!(right.key, 32);
 
     // test remove
-    avl_tree.remove(12);
+    avl_tree.remove(&12);
     println!("{:?}", &avl_tree.root);
 
     // test iter
-    let mut iter = avl_tree.iter();
     let mut data = vec![];
-    while let Some(val) = iter.next() {
+    for val in avl_tree.iter() {
       data.push(*val);
     }
     println!("{:?}", data);
   }
+
+  #[test]
+  fn test_avl_tree_lookup() {
+    let mut avl_tree = AVLTree::new();
+    for word in ["banana", "apple", "cherry"] {
+      avl_tree.insert(word.to_string());
+    }
+    assert!(avl_tree.contains("apple"));
+    assert!(!avl_tree.contains("durian"));
+    assert_eq!(avl_tree.get("cherry").map(String::as_str), Some("cherry"));
+    assert_eq!(avl_tree.take("banana"), Some("banana".to_string()));
+    assert_eq!(avl_tree.take("banana"), None);
+    assert!(avl_tree.remove("apple"));
+    assert!(!avl_tree.remove("apple"));
+    assert_eq!(avl_tree.size(), 1);
+  }
+
+  #[test]
+  fn test_avl_tree_order_statistics() {
+    let mut avl_tree = AVLTree::new();
+    for val in (0..100).map(|i| i * 2) {
+      avl_tree.insert(val);
+    }
+    for val in (0..50).map(|i| i * 4) {
+      avl_tree.remove(&val);
+    }
+    // 剩余元素为 2, 6, 10, ..., 198
+    assert_eq!(avl_tree.root.as_ref().unwrap().size, 50);
+    assert_eq!(avl_tree.rank(&2), 0);
+    assert_eq!(avl_tree.rank(&7), 2);
+    assert_eq!(avl_tree.rank(&1000), 50);
+    assert_eq!(avl_tree.select(0), Some(&2));
+    assert_eq!(avl_tree.select(10), Some(&42));
+    assert_eq!(avl_tree.select(50), None);
+    assert_eq!(avl_tree.count_in_range(6..=42), 10);
+    assert_eq!(avl_tree.count_in_range(6..42), 9);
+    assert_eq!(avl_tree.count_in_range(..), 50);
+    assert_eq!(
+      avl_tree.count_in_range((Bound::Included(100), Bound::Excluded(10))),
+      0
+    );
+  }
+
+  #[test]
+  fn test_avl_tree_range_and_cursor() {
+    let mut avl_tree = AVLTree::new();
+    for val in 0..20 {
+      avl_tree.insert(val * 10);
+    }
+    let rev: Vec<_> = avl_tree.iter().rev().copied().collect();
+    assert_eq!(rev, (0..20).rev().map(|i| i * 10).collect::<Vec<_>>());
+    let range: Vec<_> = avl_tree.range(35..=80).copied().collect();
+    assert_eq!(range, vec![40, 50, 60, 70, 80]);
+    let range: Vec<_> = avl_tree
+      .range((Bound::Excluded(40), Bound::Unbounded))
+      .rev()
+      .take(2)
+      .copied()
+      .collect();
+    assert_eq!(range, vec![190, 180]);
+    // 两端交替迭代不会越过对方
+    let mut range = avl_tree.range(..30);
+    assert_eq!(range.next(), Some(&0));
+    assert_eq!(range.next_back(), Some(&20));
+    assert_eq!(range.next_back(), Some(&10));
+    assert_eq!(range.next(), None);
+    assert_eq!(range.next_back(), None);
+    assert_eq!(avl_tree.range(50..50).next(), None);
+
+    let mut cursor = avl_tree.lower_bound(Bound::Included(&45));
+    assert_eq!((cursor.index(), cursor.current()), (Some(5), Some(&50)));
+    cursor.move_prev();
+    assert_eq!(cursor.current(), Some(&40));
+    let mut cursor = avl_tree.upper_bound(Bound::Excluded(&0));
+    assert_eq!(cursor.current(), None);
+    cursor.move_next();
+    assert_eq!(cursor.current(), Some(&0));
+    assert_eq!(
+      avl_tree.upper_bound::<i32>(Bound::Unbounded).peek_next(),
+      None
+    );
+
+    let mut cursor = avl_tree.lower_bound_mut(Bound::Excluded(&100));
+    assert_eq!(cursor.remove_current(), Some(110));
+    assert_eq!(cursor.current(), Some(&120));
+    assert!(cursor.insert(5));
+    assert_eq!(cursor.current(), Some(&120));
+    assert_eq!(cursor.peek_prev(), Some(&100));
+    assert_eq!(avl_tree.size(), 20);
+    assert!(!avl_tree.contains(&110));
+  }
+
+  #[test]
+  fn test_avl_tree_neighbors() {
+    let mut avl_tree = AVLTree::new();
+    assert_eq!(avl_tree.first(), None);
+    assert_eq!(avl_tree.pop_last(), None);
+    for val in [40, 10, 30, 20, 50] {
+      avl_tree.insert(val);
+    }
+    assert_eq!((avl_tree.first(), avl_tree.last()), (Some(&10), Some(&50)));
+    assert_eq!(avl_tree.floor(&30), Some(&30));
+    assert_eq!(avl_tree.floor(&35), Some(&30));
+    assert_eq!(avl_tree.floor(&5), None);
+    assert_eq!(avl_tree.ceiling(&35), Some(&40));
+    assert_eq!(avl_tree.ceiling(&55), None);
+    assert_eq!(avl_tree.predecessor(&30), Some(&20));
+    assert_eq!(avl_tree.successor(&30), Some(&40));
+    assert_eq!(avl_tree.successor(&50), None);
+    assert_eq!(avl_tree.pop_first(), Some(10));
+    assert_eq!(avl_tree.pop_last(), Some(50));
+    assert_eq!(
+      avl_tree.iter().copied().collect::<Vec<_>>(),
+      vec![20, 30, 40]
+    );
+    assert_eq!(avl_tree.size(), 3);
+  }
+
+  fn build(vals: impl Iterator<Item = i32>) -> AVLTree<i32> {
+    let mut tree = AVLTree::new();
+    for val in vals {
+      tree.insert(val);
+    }
+    tree
+  }
+
+  #[test]
+  fn test_avl_tree_split_join() {
+    let mut low = build(0..1000);
+    let mut high = low.split_off(&100);
+    low.validate().unwrap();
+    high.validate().unwrap();
+    assert_eq!((low.size(), high.size()), (100, 900));
+    assert_eq!((low.last(), high.first()), (Some(&99), Some(&100)));
+
+    // 高度相差较大的两棵树拼接
+    let mut small = build(-1..0);
+    small.append(&mut high);
+    small.validate().unwrap();
+    assert_eq!((small.size(), high.size()), (901, 0));
+
+    // 按第一个分量比较时，append 保留 other 中的元素
+    fn first(pair: &(i32, &str)) -> i32 {
+      pair.0
+    }
+    let mut old = AVLTree::with_comparator(ByKey(first));
+    old.extend([(1, "old"), (2, "old")]);
+    let mut new = AVLTree::with_comparator(ByKey(first));
+    new.extend([(2, "new"), (3, "new")]);
+    old.append(&mut new);
+    old.validate().unwrap();
+    assert_eq!(
+      old.iter().copied().collect::<Vec<_>>(),
+      vec![(1, "old"), (2, "new"), (3, "new")]
+    );
+
+    let union = small.union(low);
+    union.validate().unwrap();
+    assert!(union.iter().copied().eq(-1..1000));
+
+    let both = build((0..200).step_by(2)).intersection(build((0..300).step_by(3)));
+    both.validate().unwrap();
+    assert!(both.iter().copied().eq((0..200).step_by(6)));
+    let only_evens = build((0..200).step_by(2)).difference(build((0..300).step_by(3)));
+    only_evens.validate().unwrap();
+    assert!(only_evens
+      .iter()
+      .copied()
+      .eq((0..200).step_by(2).filter(|v| v % 3 != 0)));
+    assert_eq!(only_evens.size(), 66);
+  }
+
+  #[test]
+  fn test_avl_tree_traits() {
+    let mut avl_tree: AVLTree<i32> = (0..10).rev().collect();
+    assert_eq!(format!("{:?}", avl_tree), "{0, 1, 2, 3, 4, 5, 6, 7, 8, 9}");
+    let mut iter = avl_tree.iter();
+    assert_eq!(iter.len(), 10);
+    iter.next();
+    iter.next_back();
+    assert_eq!(iter.size_hint(), (8, Some(8)));
+    assert_eq!(avl_tree.range(3..7).len(), 4);
+
+    let copy = avl_tree.clone();
+    assert_eq!(copy, avl_tree);
+    avl_tree.extend(&[10, 11]);
+    assert_ne!(copy, avl_tree);
+    assert!(copy < avl_tree);
+    let hash = |tree: &AVLTree<i32>| {
+      let mut hasher = std::collections::hash_map::DefaultHasher::new();
+      std::hash::Hash::hash(tree, &mut hasher);
+      std::hash::Hasher::finish(&hasher)
+    };
+    assert_eq!(hash(&copy), hash(&copy.clone()));
+
+    avl_tree.retain(|val| val % 3 == 0);
+    avl_tree.validate().unwrap();
+    assert_eq!(
+      avl_tree.iter().copied().collect::<Vec<_>>(),
+      vec![0, 3, 6, 9]
+    );
+    let drained: Vec<_> = avl_tree.drain().rev().collect();
+    assert_eq!(drained, vec![9, 6, 3, 0]);
+    assert!(avl_tree.is_empty());
+    let owned: Vec<_> = copy.into_iter().collect();
+    assert_eq!(owned, (0..10).collect::<Vec<_>>());
+    assert_eq!(AVLTree::<i32>::default().size(), 0);
+  }
+
+  #[test]
+  fn test_avl_tree_from_sorted() {
+    for n in [0, 1, 2, 7, 100, 1023, 1024] {
+      let avl_tree = AVLTree::from_sorted_iter(0..n).unwrap();
+      avl_tree.validate().unwrap();
+      assert_eq!(avl_tree.size(), n as usize);
+      assert!(avl_tree.iter().copied().eq(0..n));
+    }
+    let avl_tree = AVLTree::from_sorted_iter([1, 1, 2, 3, 3, 3]).unwrap();
+    assert_eq!(avl_tree.iter().copied().collect::<Vec<_>>(), vec![1, 2, 3]);
+    assert_eq!(
+      AVLTree::from_sorted_iter([1, 3, 2]).unwrap_err(),
+      OutOfOrder { index: 2 }
+    );
+    let avl_tree: AVLTree<_> = [5, 3, 5, 1].into_iter().collect();
+    avl_tree.validate().unwrap();
+    assert_eq!(avl_tree.iter().copied().collect::<Vec<_>>(), vec![1, 3, 5]);
+  }
+
+  #[test]
+  fn test_avl_tree_comparator() {
+    let mut desc = AVLTree::reversed();
+    desc.extend([3, 1, 4, 1, 5, 9, 2, 6]);
+    assert_eq!(
+      desc.iter().copied().collect::<Vec<_>>(),
+      vec![9, 6, 5, 4, 3, 2, 1]
+    );
+    assert_eq!(desc.first(), Some(&9));
+    assert_eq!(
+      desc
+        .range((Bound::Included(6), Bound::Included(2)))
+        .copied()
+        .collect::<Vec<_>>(),
+      vec![6, 5, 4, 3, 2]
+    );
+    assert_eq!(desc.rank(&5), 2);
+
+    let mut words = AVLTree::by_key(|s: &String| s.to_lowercase());
+    for word in ["Banana", "apple", "APPLE", "cherry"] {
+      words.insert(word.to_string());
+    }
+    assert_eq!(words.size(), 3);
+    assert_eq!(
+      words.get(&"BANANA".to_string()).map(String::as_str),
+      Some("Banana")
+    );
+    assert_eq!(words.first().map(String::as_str), Some("apple"));
+
+    let mut by_len =
+      AVLTree::with_comparator(|a: &&str, b: &&str| a.len().cmp(&b.len()).then(a.cmp(b)));
+    by_len.extend(["ccc", "a", "bb", "aa"]);
+    assert_eq!(
+      by_len.iter().copied().collect::<Vec<_>>(),
+      vec!["a", "aa", "bb", "ccc"]
+    );
+    let high = by_len.split_off(&"bb");
+    high.validate().unwrap();
+    assert_eq!(high.iter().copied().collect::<Vec<_>>(), vec!["bb", "ccc"]);
+
+    let collected: AVLTree<i32, Reversed> = (0..5).collect();
+    assert_eq!(
+      collected.iter().copied().collect::<Vec<_>>(),
+      vec![4, 3, 2, 1, 0]
+    );
+    assert!(AVLTree::from_sorted_iter_with([3, 2, 1], Reversed).is_ok());
+  }
+
+  #[test]
+  fn test_avl_tree_validate() {
+    let mut avl_tree = build([20, 10, 30, 5].into_iter());
+    assert_eq!(avl_tree.validate(), Ok(()));
+    assert_eq!(
+      avl_tree.pretty(),
+      "20 (h=3, n=4)\n├── L: 10 (h=2, n=2)\n│   └── L: 5 (h=1, n=1)\n└── R: 30 (h=1, n=1)\n"
+    );
+    let dot = avl_tree.to_dot();
+    assert!(dot.starts_with("digraph AVLTree {\n  n0 [label=\"20\\nh=3 n=4\"];"));
+    assert!(dot.contains("n1 -> n2 [label=\"L\"];"));
+    assert!(dot.contains("n0 -> n3 [label=\"R\"];"));
+
+    avl_tree.length = 3;
+    assert_eq!(
+      avl_tree.validate(),
+      Err(AVLViolation::LengthMismatch {
+        length: 3,
+        actual: 4
+      })
+    );
+    avl_tree.length = 4;
+    let root = avl_tree.root.as_mut().unwrap();
+    root.right.as_mut().unwrap().key = 15;
+    assert_eq!(
+      avl_tree.validate(),
+      Err(AVLViolation::Unordered { index: 3 })
+    );
+    let root = avl_tree.root.as_mut().unwrap();
+    root.right.as_mut().unwrap().key = 30;
+    root.height = 4;
+    assert_eq!(
+      avl_tree.validate(),
+      Err(AVLViolation::HeightMismatch {
+        index: 2,
+        stored: 4,
+        actual: 3
+      })
+    );
+    let root = avl_tree.root.as_mut().unwrap();
+    root.height = 3;
+    root.right = None;
+    root.size = 3;
+    assert_eq!(
+      avl_tree.validate(),
+      Err(AVLViolation::Unbalanced {
+        index: 2,
+        balance_factor: -2
+      })
+    );
+  }
+
+  #[test]
+  fn test_iterative_matches_recursive() {
+    let (mut iterative, mut recursive): (Tree, Tree) = (None, None);
+    for key in keys(500).chain(keys(100)) {
+      assert_eq!(
+        insert(&mut iterative, key, (), &Natural).is_ok(),
+        insert_recursive(&mut recursive, key)
+      );
+    }
+    for key in keys(600).filter(|k| k % 3 != 0) {
+      assert_eq!(
+        remove(&mut iterative, &key, &Natural).is_some(),
+        remove_recursive(&mut recursive, key)
+      );
+      let (mut a, mut b) = (Vec::new(), Vec::new());
+      shape(&iterative, &mut a);
+      shape(&recursive, &mut b);
+      assert_eq!(a, b);
+    }
+    while let Some(node) = take_min(&mut iterative) {
+      assert_eq!(
+        take_min_recursive(&mut recursive).map(|n| n.key),
+        Some(node.key)
+      );
+    }
+    assert!(recursive.is_none());
+    // unlink 通过迭代版本的 take_min 取右子树最小节点
+    let mut tree: Tree = None;
+    keys(7).for_each(|k| _ = insert(&mut tree, k, (), &Natural));
+    let mut root = tree.take().unwrap();
+    let mut rest = Vec::new();
+    flatten(unlink(&mut root), &mut rest);
+    let expected: Vec<_> = (0..7).filter(|&k| k != root.key).collect();
+    assert_eq!(
+      rest.into_iter().map(|(k, _)| k).collect::<Vec<_>>(),
+      expected
+    );
+  }
+
+  #[test]
+  fn test_insert_vacant() {
+    for n in [1, 2, 3, 10, 300] {
+      let orders: [Vec<i32>; 3] = [keys(n).collect(), (0..n).collect(), (0..n).rev().collect()];
+      for order in orders {
+        let mut expected: Tree = None;
+        let mut avl_tree = AVLTree::new();
+        for key in order {
+          _ = insert(&mut expected, key, (), &Natural);
+          let Search::Vacant(critical) = search_mut(&mut avl_tree.root, &key, &Natural) else {
+            panic!("{key} is not in the tree yet");
+          };
+          insert_vacant(&mut avl_tree.root, key, (), critical, &Natural);
+          avl_tree.length += 1;
+          assert_eq!(avl_tree.validate(), Ok(()));
+          // 预先旋转的自顶向下插入与自底向上回溯的插入得到相同的树
+          let (mut a, mut b) = (Vec::new(), Vec::new());
+          shape(&avl_tree.root, &mut a);
+          shape(&expected, &mut b);
+          assert_eq!(a, b);
+        }
+        assert!(matches!(
+          search_mut(&mut avl_tree.root, &(n - 1), &Natural),
+          Search::Found(node) if node.key == n - 1
+        ));
+      }
+    }
+  }
+
+  // cargo test --release -- --ignored --nocapture bench_iterative_vs_recursive
+  #[test]
+  #[ignore]
+  fn bench_iterative_vs_recursive() {
+    const N: i32 = 1_000_000;
+    let mut tree: Tree = None;
+    let start = Instant::now();
+    keys(N).for_each(|k| _ = insert(&mut tree, k, (), &Natural));
+    let insert_time = start.elapsed();
+    let start = Instant::now();
+    keys(N).for_each(|k| _ = remove(&mut tree, &k, &Natural));
+    let remove_time = start.elapsed();
+    println!("iterative: insert {insert_time:?}, remove {remove_time:?}");
+
+    let start = Instant::now();
+    keys(N).for_each(|k| _ = insert_recursive(&mut tree, k));
+    let insert_time = start.elapsed();
+    let start = Instant::now();
+    keys(N).for_each(|k| _ = remove_recursive(&mut tree, k));
+    let remove_time = start.elapsed();
+    println!("recursive: insert {insert_time:?}, remove {remove_time:?}");
+    assert!(mem::take(&mut tree).is_none());
+  }
+
+  #[cfg(feature = "serde")]
+  #[test]
+  fn test_serde() {
+    let avl_tree: AVLTree<i32> = (0..10).rev().collect();
+    let json = serde_json::to_string(&avl_tree).unwrap();
+    assert_eq!(json, "[0,1,2,3,4,5,6,7,8,9]");
+    let decoded: AVLTree<i32> = serde_json::from_str(&json).unwrap();
+    decoded.validate().unwrap();
+    assert_eq!(decoded, avl_tree);
+    let reversed: AVLTree<i32, Reversed> = serde_json::from_str("[3,2,1]").unwrap();
+    assert_eq!(reversed.first(), Some(&3));
+    let err = serde_json::from_str::<AVLTree<i32>>("[1,3,2]").unwrap_err();
+    assert!(err.to_string().contains("index 2"));
+    let err = serde_json::from_str::<AVLTree<i32>>("[1,1]").unwrap_err();
+    assert!(err.to_string().contains("index 1"));
+    let err = serde_json::from_str::<AVLTree<i32>>("[2,1]").unwrap_err();
+    assert!(err.to_string().contains("index 1"));
+  }
 }
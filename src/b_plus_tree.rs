@@ -0,0 +1,544 @@
+use std::{
+  borrow::Borrow,
+  mem,
+  ops::{Bound, RangeBounds},
+};
+
+// 节点存放在 arena 中，用下标互相引用，叶子通过 next 串成有序链表
+#[derive(Debug)]
+enum Node<K, V> {
+  // 第 i 个孩子中的键 k 满足 keys[i - 1] <= k < keys[i]，分隔键只用于路由
+  Internal {
+    keys: Vec<K>,
+    children: Vec<usize>,
+  },
+  Leaf {
+    keys: Vec<K>,
+    values: Vec<V>,
+    next: Option<usize>,
+  },
+}
+
+impl<K, V> Node<K, V> {
+  fn empty_leaf() -> Self {
+    Node::Leaf {
+      keys: Vec::new(),
+      values: Vec::new(),
+      next: None,
+    }
+  }
+
+  fn keys(&self) -> &Vec<K> {
+    match self {
+      Node::Internal { keys, .. } | Node::Leaf { keys, .. } => keys,
+    }
+  }
+}
+
+// 所有键值对都存放在叶子中，内部节点只保存分隔键，范围扫描沿叶子链表前进而不需要回到上层。
+// t 为最小度数，与 BTree::new(t) 相同：除根以外的节点有 t-1 到 2t-1 个键
+#[derive(Debug)]
+pub struct BPlusTree<K, V> {
+  nodes: Vec<Node<K, V>>,
+  free: Vec<usize>, // 已释放的节点下标，分配时优先复用
+  root: usize,
+  first_leaf: usize,
+  max_keys: usize,
+  min_keys: usize,
+  length: usize,
+}
+
+// 插入导致节点分裂时，返回上移的分隔键与新建的右侧节点
+type Split<K> = Option<(K, usize)>;
+
+impl<K: Ord + Clone, V> BPlusTree<K, V> {
+  pub fn new(t: usize) -> Self {
+    assert!(t >= 2, "minimum degree must be at least 2");
+    Self {
+      nodes: vec![Node::empty_leaf()],
+      free: Vec::new(),
+      root: 0,
+      first_leaf: 0,
+      max_keys: 2 * t - 1,
+      min_keys: t - 1,
+      length: 0,
+    }
+  }
+
+  pub fn len(&self) -> usize {
+    self.length
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.length == 0
+  }
+
+  fn alloc(&mut self, node: Node<K, V>) -> usize {
+    match self.free.pop() {
+      Some(id) => {
+        self.nodes[id] = node;
+        id
+      }
+      None => {
+        self.nodes.push(node);
+        self.nodes.len() - 1
+      }
+    }
+  }
+
+  fn release(&mut self, id: usize) -> Node<K, V> {
+    self.free.push(id);
+    mem::replace(&mut self.nodes[id], Node::empty_leaf())
+  }
+
+  // 从根向下找到可能包含 key 的叶子
+  fn find_leaf<Q>(&self, key: &Q) -> usize
+  where
+    K: Borrow<Q>,
+    Q: Ord + ?Sized,
+  {
+    let mut id = self.root;
+    while let Node::Internal { keys, children } = &self.nodes[id] {
+      id = children[keys.partition_point(|k| k.borrow() <= key)];
+    }
+    id
+  }
+
+  pub fn get<Q>(&self, key: &Q) -> Option<&V>
+  where
+    K: Borrow<Q>,
+    Q: Ord + ?Sized,
+  {
+    match &self.nodes[self.find_leaf(key)] {
+      Node::Leaf { keys, values, .. } => {
+        let index = keys.binary_search_by(|k| k.borrow().cmp(key)).ok()?;
+        Some(&values[index])
+      }
+      Node::Internal { .. } => unreachable!(),
+    }
+  }
+
+  pub fn contains_key<Q>(&self, key: &Q) -> bool
+  where
+    K: Borrow<Q>,
+    Q: Ord + ?Sized,
+  {
+    self.get(key).is_some()
+  }
+
+  // 插入键值对，键已存在时替换值并返回旧值
+  pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+    let (old, split) = self.insert_into(self.root, key, value);
+    // 根节点分裂时树长高一层
+    if let Some((separator, right)) = split {
+      let root = Node::Internal {
+        keys: vec![separator],
+        children: vec![self.root, right],
+      };
+      self.root = self.alloc(root);
+    }
+    if old.is_none() {
+      self.length += 1;
+    }
+    old
+  }
+
+  fn insert_into(&mut self, id: usize, key: K, value: V) -> (Option<V>, Split<K>) {
+    let max_keys = self.max_keys;
+    match &mut self.nodes[id] {
+      Node::Leaf { keys, values, next } => {
+        let index = match keys.binary_search(&key) {
+          Ok(index) => return (Some(mem::replace(&mut values[index], value)), None),
+          Err(index) => index,
+        };
+        keys.insert(index, key);
+        values.insert(index, value);
+        if keys.len() <= max_keys {
+          return (None, None);
+        }
+        // 叶子分裂，右半部分的第一个键复制到父节点作为分隔键
+        let mid = keys.len() / 2;
+        let right = Node::Leaf {
+          keys: keys.split_off(mid),
+          values: values.split_off(mid),
+          next: next.take(),
+        };
+        let separator = right.keys()[0].clone();
+        let right = self.alloc(right);
+        if let Node::Leaf { next, .. } = &mut self.nodes[id] {
+          *next = Some(right);
+        }
+        (None, Some((separator, right)))
+      }
+      Node::Internal { keys, children } => {
+        let index = keys.partition_point(|k| *k <= key);
+        let child = children[index];
+        let (old, split) = self.insert_into(child, key, value);
+        let Some((separator, right)) = split else {
+          return (old, None);
+        };
+        let Node::Internal { keys, children } = &mut self.nodes[id] else {
+          unreachable!()
+        };
+        keys.insert(index, separator);
+        children.insert(index + 1, right);
+        if keys.len() <= max_keys {
+          return (old, None);
+        }
+        // 内部节点分裂，中间的分隔键移动到父节点
+        let mid = keys.len() / 2;
+        let right_keys = keys.split_off(mid + 1);
+        let separator = keys.pop().unwrap();
+        let right_children = children.split_off(mid + 1);
+        let right = self.alloc(Node::Internal {
+          keys: right_keys,
+          children: right_children,
+        });
+        (old, Some((separator, right)))
+      }
+    }
+  }
+
+  pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+  where
+    K: Borrow<Q>,
+    Q: Ord + ?Sized,
+  {
+    let removed = self.remove_from(self.root, key)?;
+    self.length -= 1;
+    // 根节点只剩一个孩子时树降低一层
+    if let Node::Internal { keys, children } = &self.nodes[self.root] {
+      if keys.is_empty() {
+        let child = children[0];
+        self.release(self.root);
+        self.root = child;
+      }
+    }
+    Some(removed)
+  }
+
+  fn remove_from<Q>(&mut self, id: usize, key: &Q) -> Option<V>
+  where
+    K: Borrow<Q>,
+    Q: Ord + ?Sized,
+  {
+    match &mut self.nodes[id] {
+      Node::Leaf { keys, values, .. } => {
+        let index = keys.binary_search_by(|k| k.borrow().cmp(key)).ok()?;
+        keys.remove(index);
+        Some(values.remove(index))
+      }
+      Node::Internal { keys, children } => {
+        let index = keys.partition_point(|k| k.borrow() <= key);
+        let child = children[index];
+        let removed = self.remove_from(child, key)?;
+        self.fix_child(id, index);
+        Some(removed)
+      }
+    }
+  }
+
+  // 第 index 个孩子的键少于 t-1 个时，先尝试从相邻兄弟借一个，否则与兄弟合并
+  fn fix_child(&mut self, parent: usize, index: usize) {
+    let Node::Internal { children, .. } = &self.nodes[parent] else {
+      unreachable!()
+    };
+    let child = children[index];
+    let left = index.checked_sub(1).map(|i| children[i]);
+    let right = children.get(index + 1).copied();
+    if self.nodes[child].keys().len() >= self.min_keys {
+      return;
+    }
+    let spare = |id: usize| self.nodes[id].keys().len() > self.min_keys;
+    match (left, right) {
+      (Some(left), _) if spare(left) => self.borrow_from_left(parent, index, left, child),
+      (_, Some(right)) if spare(right) => self.borrow_from_right(parent, index, child, right),
+      (Some(left), _) => self.merge(parent, index - 1, left, child),
+      (None, Some(right)) => self.merge(parent, index, child, right),
+      (None, None) => unreachable!("internal nodes have at least two children"),
+    }
+  }
+
+  // 同时取得两个不同节点的可变引用
+  fn pair_mut(&mut self, a: usize, b: usize) -> (&mut Node<K, V>, &mut Node<K, V>) {
+    if a < b {
+      let (left, right) = self.nodes.split_at_mut(b);
+      (&mut left[a], &mut right[0])
+    } else {
+      let (left, right) = self.nodes.split_at_mut(a);
+      (&mut right[0], &mut left[b])
+    }
+  }
+
+  fn separator_mut(&mut self, parent: usize, index: usize) -> &mut K {
+    match &mut self.nodes[parent] {
+      Node::Internal { keys, .. } => &mut keys[index],
+      Node::Leaf { .. } => unreachable!(),
+    }
+  }
+
+  // 叶子直接移动一个键值对并用右侧节点的最小键更新分隔键；
+  // 内部节点则让父节点的分隔键下移、兄弟的边界键上移
+  fn borrow_from_left(&mut self, parent: usize, index: usize, left: usize, child: usize) {
+    let separator = match self.pair_mut(left, child) {
+      (
+        Node::Leaf {
+          keys: left_keys,
+          values: left_values,
+          ..
+        },
+        Node::Leaf { keys, values, .. },
+      ) => {
+        keys.insert(0, left_keys.pop().unwrap());
+        values.insert(0, left_values.pop().unwrap());
+        keys[0].clone()
+      }
+      (
+        Node::Internal {
+          keys: left_keys,
+          children: left_children,
+        },
+        Node::Internal { children, .. },
+      ) => {
+        children.insert(0, left_children.pop().unwrap());
+        let up = left_keys.pop().unwrap();
+        let down = mem::replace(self.separator_mut(parent, index - 1), up);
+        if let Node::Internal { keys, .. } = &mut self.nodes[child] {
+          keys.insert(0, down);
+        }
+        return;
+      }
+      _ => unreachable!("siblings must be on the same level"),
+    };
+    *self.separator_mut(parent, index - 1) = separator;
+  }
+
+  fn borrow_from_right(&mut self, parent: usize, index: usize, child: usize, right: usize) {
+    let separator = match self.pair_mut(child, right) {
+      (
+        Node::Leaf { keys, values, .. },
+        Node::Leaf {
+          keys: right_keys,
+          values: right_values,
+          ..
+        },
+      ) => {
+        keys.push(right_keys.remove(0));
+        values.push(right_values.remove(0));
+        right_keys[0].clone()
+      }
+      (
+        Node::Internal { children, .. },
+        Node::Internal {
+          keys: right_keys,
+          children: right_children,
+        },
+      ) => {
+        children.push(right_children.remove(0));
+        let up = right_keys.remove(0);
+        let down = mem::replace(self.separator_mut(parent, index), up);
+        if let Node::Internal { keys, .. } = &mut self.nodes[child] {
+          keys.push(down);
+        }
+        return;
+      }
+      _ => unreachable!("siblings must be on the same level"),
+    };
+    *self.separator_mut(parent, index) = separator;
+  }
+
+  // 将 right 并入 left，并删除父节点中它们之间的分隔键
+  fn merge(&mut self, parent: usize, index: usize, left: usize, right: usize) {
+    let Node::Internal { keys, children } = &mut self.nodes[parent] else {
+      unreachable!()
+    };
+    let separator = keys.remove(index);
+    children.remove(index + 1);
+    match (self.release(right), &mut self.nodes[left]) {
+      (
+        Node::Leaf {
+          keys: right_keys,
+          values: right_values,
+          next: right_next,
+        },
+        Node::Leaf { keys, values, next },
+      ) => {
+        keys.extend(right_keys);
+        values.extend(right_values);
+        *next = right_next;
+      }
+      (
+        Node::Internal {
+          keys: right_keys,
+          children: right_children,
+        },
+        Node::Internal { keys, children },
+      ) => {
+        keys.push(separator);
+        keys.extend(right_keys);
+        children.extend(right_children);
+      }
+      _ => unreachable!("siblings must be on the same level"),
+    }
+  }
+
+  pub fn iter(&self) -> Range<'_, K, V> {
+    self.range(..)
+  }
+
+  // 找到下界所在的叶子后沿叶子链表顺序扫描
+  pub fn range<R: RangeBounds<K>>(&self, range: R) -> Range<'_, K, V> {
+    let (leaf, index) = match range.start_bound() {
+      Bound::Included(start) => {
+        let leaf = self.find_leaf(start);
+        (leaf, self.nodes[leaf].keys().partition_point(|k| k < start))
+      }
+      Bound::Excluded(start) => {
+        let leaf = self.find_leaf(start);
+        (
+          leaf,
+          self.nodes[leaf].keys().partition_point(|k| k <= start),
+        )
+      }
+      Bound::Unbounded => (self.first_leaf, 0),
+    };
+    Range {
+      nodes: &self.nodes,
+      leaf: Some(leaf),
+      index,
+      end: range.end_bound().cloned(),
+    }
+  }
+}
+
+pub struct Range<'a, K, V> {
+  nodes: &'a [Node<K, V>],
+  leaf: Option<usize>,
+  index: usize,
+  end: Bound<K>,
+}
+
+impl<'a, K: Ord, V> Iterator for Range<'a, K, V> {
+  type Item = (&'a K, &'a V);
+
+  fn next(&mut self) -> Option<Self::Item> {
+    loop {
+      let Node::Leaf { keys, values, next } = &self.nodes[self.leaf?] else {
+        unreachable!()
+      };
+      if self.index < keys.len() {
+        let key = &keys[self.index];
+        let in_range = match &self.end {
+          Bound::Included(end) => key <= end,
+          Bound::Excluded(end) => key < end,
+          Bound::Unbounded => true,
+        };
+        if !in_range {
+          self.leaf = None;
+          return None;
+        }
+        self.index += 1;
+        return Some((key, &values[self.index - 1]));
+      }
+      // 当前叶子扫描完毕，转到下一个叶子
+      self.leaf = *next;
+      self.index = 0;
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{BPlusTree, Node};
+  use std::{collections::BTreeMap, ops::Bound};
+
+  // 检查键数范围、叶子深度一致与分隔键的路由区间，返回子树中叶子的深度
+  fn check_node(
+    tree: &BPlusTree<i32, i32>,
+    id: usize,
+    low: Option<i32>,
+    high: Option<i32>,
+    leaves: &mut Vec<usize>,
+  ) -> usize {
+    let keys = tree.nodes[id].keys();
+    assert!(keys.len() <= tree.max_keys);
+    if id != tree.root {
+      assert!(keys.len() >= tree.min_keys);
+    }
+    assert!(keys.windows(2).all(|w| w[0] < w[1]));
+    assert!(keys.iter().all(|k| low.is_none_or(|low| low <= *k)));
+    assert!(keys.iter().all(|k| high.is_none_or(|high| *k < high)));
+    match &tree.nodes[id] {
+      Node::Leaf { keys, values, .. } => {
+        assert_eq!(keys.len(), values.len());
+        leaves.push(id);
+        0
+      }
+      Node::Internal { keys, children } => {
+        assert_eq!(children.len(), keys.len() + 1);
+        let depths: Vec<_> = children
+          .iter()
+          .enumerate()
+          .map(|(i, &child)| {
+            let low = if i == 0 { low } else { Some(keys[i - 1]) };
+            let high = keys.get(i).copied().or(high);
+            check_node(tree, child, low, high, leaves)
+          })
+          .collect();
+        assert!(depths.windows(2).all(|w| w[0] == w[1]));
+        depths[0] + 1
+      }
+    }
+  }
+
+  fn check(tree: &BPlusTree<i32, i32>, expected: &BTreeMap<i32, i32>) {
+    let mut leaves = Vec::new();
+    check_node(tree, tree.root, None, None, &mut leaves);
+    // 叶子链表的顺序与树中从左到右的顺序一致
+    assert_eq!(leaves[0], tree.first_leaf);
+    for pair in leaves.windows(2) {
+      let Node::Leaf { next, .. } = &tree.nodes[pair[0]] else {
+        unreachable!()
+      };
+      assert_eq!(*next, Some(pair[1]));
+    }
+    assert_eq!(tree.len(), expected.len());
+    assert!(tree.iter().eq(expected.iter()));
+  }
+
+  #[test]
+  fn test_b_plus_tree() {
+    for t in [2, 3, 5] {
+      let mut tree = BPlusTree::new(t);
+      let mut expected = BTreeMap::new();
+      for i in 0..400 {
+        let key = (i * 37) % 211;
+        assert_eq!(tree.insert(key, i), expected.insert(key, i));
+        check(&tree, &expected);
+      }
+      assert_eq!(tree.get(&37), expected.get(&37));
+      assert_eq!(tree.get(&1000), None);
+      for range in [
+        (Bound::Included(10), Bound::Excluded(50)),
+        (Bound::Excluded(10), Bound::Included(50)),
+        (Bound::Unbounded, Bound::Excluded(7)),
+        (Bound::Included(200), Bound::Unbounded),
+        (Bound::Included(300), Bound::Unbounded),
+      ] {
+        assert!(tree.range(range).eq(expected.range(range)));
+      }
+      for i in 0..300 {
+        let key = (i * 53) % 227;
+        assert_eq!(tree.remove(&key), expected.remove(&key));
+        check(&tree, &expected);
+      }
+      assert!(tree.is_empty());
+      // 释放的节点被复用，不会无限增长
+      let allocated = tree.nodes.len();
+      for i in 0..400 {
+        tree.insert((i * 37) % 211, i);
+      }
+      assert_eq!(tree.nodes.len(), allocated);
+    }
+  }
+}
@@ -1,52 +1,110 @@
-use std::{fmt::Debug, mem};
+use std::{
+  borrow::Borrow,
+  cmp::Ordering,
+  error::Error,
+  fmt::{self, Debug, Display, Write},
+  iter::FusedIterator,
+  marker::PhantomData,
+  mem,
+  ops::{Bound, RangeBounds},
+  ptr, vec,
+};
+
+pub use crate::avl_tree::OutOfOrder;
 
 #[derive(Debug)]
-struct Node<T> {
-  keys: Vec<T>,
-  children: Vec<Node<T>>,
+pub(crate) struct Node<T> {
+  pub(crate) keys: Vec<T>,
+  pub(crate) children: Vec<Node<T>>,
 }
 
-impl<T: Ord> Node<T> {
-  fn new(degree: usize, _keys: Option<Vec<T>>, _children: Option<Vec<Node<T>>>) -> Self {
+impl<T> Node<T> {
+  pub(crate) fn new(degree: usize, _keys: Option<Vec<T>>, _children: Option<Vec<Node<T>>>) -> Self {
     Self {
       keys: _keys.unwrap_or_else(|| Vec::with_capacity(degree - 1)),
       children: _children.unwrap_or_else(|| Vec::with_capacity(degree)),
     }
   }
 
-  fn is_leaf(&self) -> bool {
+  pub(crate) fn is_leaf(&self) -> bool {
     self.children.is_empty()
   }
 }
 
+/// 节点内查找关键字的策略，cmp 返回节点中的关键字与目标的大小关系，
+/// 找到时返回 Ok(位置)，否则返回 Err(应当下降的孩子位置)，与 slice::binary_search_by 一致
+pub trait SearchStrategy {
+  fn search<T, F: Fn(&T) -> Ordering>(keys: &[T], cmp: F) -> Result<usize, usize>;
+}
+
+/// 从左向右顺序比较，度数较小时常数最小
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Linear;
+
+impl SearchStrategy for Linear {
+  fn search<T, F: Fn(&T) -> Ordering>(keys: &[T], cmp: F) -> Result<usize, usize> {
+    for (index, key) in keys.iter().enumerate() {
+      match cmp(key) {
+        Ordering::Less => continue,
+        Ordering::Equal => return Ok(index),
+        Ordering::Greater => return Err(index),
+      }
+    }
+    Err(keys.len())
+  }
+}
+
+/// 二分查找，度数较大时比较次数为 O(log t)，默认策略
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Binary;
+
+impl SearchStrategy for Binary {
+  fn search<T, F: Fn(&T) -> Ordering>(keys: &[T], cmp: F) -> Result<usize, usize> {
+    keys.binary_search_by(cmp)
+  }
+}
+
+/// 不提前退出地统计小于目标的关键字个数，循环体没有分支，
+/// 小整数键的比较可以被编译器自动向量化
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Branchless;
+
+impl SearchStrategy for Branchless {
+  fn search<T, F: Fn(&T) -> Ordering>(keys: &[T], cmp: F) -> Result<usize, usize> {
+    let index = keys.iter().map(|key| cmp(key).is_lt() as usize).sum();
+    match keys.get(index) {
+      Some(key) if cmp(key).is_eq() => Ok(index),
+      _ => Err(index),
+    }
+  }
+}
+
 #[derive(Debug)]
-struct BTreeProps {
-  degree: usize,         // B树的阶数或度数 2*t
-  max_keys: usize,       // 最大关键字数 2*t-1
-  min_keys_index: usize, // 最小关键字数，作为索引用于分割子节点(向下取整) [(2*t-1)/2]=t-1
+pub(crate) struct BTreeProps<S = Binary> {
+  pub(crate) degree: usize, // B树的阶数或度数 2*t
+  max_keys: usize,          // 最大关键字数 2*t-1
+  min_keys_index: usize,    // 最小关键字数，作为索引用于分割子节点(向下取整) [(2*t-1)/2]=t-1
+  strategy: PhantomData<S>, // 节点内的查找策略
 }
 
-impl BTreeProps {
-  fn new(degree: usize) -> Self {
+impl<S: SearchStrategy> BTreeProps<S> {
+  pub(crate) fn new(degree: usize) -> Self {
     Self {
       degree,
       max_keys: degree - 1,
       min_keys_index: (degree - 1) / 2,
+      strategy: PhantomData,
     }
   }
   // 关键字数是否已经达到最大值
-  fn is_maxed_out<T: Ord + Copy>(&self, node: &Node<T>) -> bool {
+  fn is_maxed_out<T>(&self, node: &Node<T>) -> bool {
     node.keys.len() == self.max_keys
   }
-  // 将子节点分割成两个子节点
-  fn split_child<T: Ord + Copy + Default>(&self, parent: &mut Node<T>, child_index: usize) {
+  // 将子节点分割成两个子节点，中间关键字移动到父节点
+  fn split_child<T>(&self, parent: &mut Node<T>, child_index: usize) {
     let child = &mut parent.children[child_index];
-    let middle_key = child.keys[self.min_keys_index];
-
-    let right_keys = match child.keys.split_off(self.min_keys_index).split_first() {
-      Some((_first, _others)) => _others.to_vec(),
-      None => Vec::with_capacity(self.max_keys),
-    };
+    let right_keys = child.keys.split_off(self.min_keys_index + 1);
+    let middle_key = child.keys.pop().unwrap();
     let right_children = if !child.is_leaf() {
       Some(child.children.split_off(self.min_keys_index + 1))
     } else {
@@ -58,15 +116,41 @@ impl BTreeProps {
     parent.children.insert(child_index + 1, new_child_node);
   }
 
-  fn insert_non_full<T: Ord + Copy + Default>(&mut self, node: &mut Node<T>, key: T) {
-    let mut index: usize = node.keys.len();
-    // 比较关键字大小，寻找合适的插入位置
-    while index >= 1 && node.keys[index - 1] > key {
-      index -= 1;
+  // 插入关键字，根节点已满时先创建新根节点并分裂原根节点作为它的孩子。
+  // 分裂在下降前完成，插入后关键字不再移动，因此可以返回它的引用
+  pub(crate) fn insert_root<'a, T: Ord>(&mut self, root: &'a mut Node<T>, key: T) -> &'a mut T {
+    self.split_full_root(root);
+    self.insert_non_full(root, key)
+  }
+
+  // 与 insert_root 相同，但不允许重复：下降途中遇到相等的关键字时返回它与传入的关键字，
+  // 此前做过的分裂仍然保留，树依然合法
+  pub(crate) fn insert_unique_root<'a, T: Ord>(
+    &mut self,
+    root: &'a mut Node<T>,
+    key: T,
+  ) -> Result<&'a mut T, (&'a mut T, T)> {
+    self.split_full_root(root);
+    self.insert_unique(root, key)
+  }
+
+  fn split_full_root<T>(&mut self, root: &mut Node<T>) {
+    if self.is_maxed_out(root) {
+      let mut new_root = Node::new(self.degree, None, None);
+      // 交换后new_root为原根节点，root为新根节点
+      mem::swap(&mut new_root, root);
+      root.children.insert(0, new_root);
+      self.split_child(root, 0);
     }
+  }
+
+  fn insert_non_full<'a, T: Ord>(&mut self, node: &'a mut Node<T>, key: T) -> &'a mut T {
+    // 比较关键字大小，寻找合适的插入位置，相同的关键字允许重复插入
+    let (Ok(mut index) | Err(mut index)) = Self::locate(node, &|k: &T| k.cmp(&key));
 
     if node.is_leaf() {
       node.keys.insert(index, key);
+      &mut node.keys[index]
     } else {
       if self.is_maxed_out(&node.children[index]) {
         self.split_child(node, index);
@@ -74,83 +158,952 @@ impl BTreeProps {
           index += 1;
         }
       }
-      self.insert_non_full(&mut node.children[index], key);
+      self.insert_non_full(&mut node.children[index], key)
+    }
+  }
+
+  fn insert_unique<'a, T: Ord>(
+    &mut self,
+    node: &'a mut Node<T>,
+    key: T,
+  ) -> Result<&'a mut T, (&'a mut T, T)> {
+    let mut index = match Self::locate(node, &|k: &T| k.cmp(&key)) {
+      Ok(index) => return Err((&mut node.keys[index], key)),
+      Err(index) => index,
+    };
+    if node.is_leaf() {
+      node.keys.insert(index, key);
+      return Ok(&mut node.keys[index]);
+    }
+    if self.is_maxed_out(&node.children[index]) {
+      self.split_child(node, index);
+      // 上移的中间关键字也可能就是要找的关键字
+      match node.keys[index].cmp(&key) {
+        Ordering::Equal => return Err((&mut node.keys[index], key)),
+        Ordering::Less => index += 1,
+        Ordering::Greater => {}
+      }
+    }
+    self.insert_unique(&mut node.children[index], key)
+  }
+  // 按查找策略在节点内查找关键字
+  fn locate<T, F: Fn(&T) -> Ordering>(node: &Node<T>, cmp: &F) -> Result<usize, usize> {
+    S::search(&node.keys, cmp)
+  }
+  // 从根向下查找关键字
+  pub(crate) fn search_node<'a, T, F: Fn(&T) -> Ordering>(
+    &self,
+    mut node: &'a Node<T>,
+    cmp: &F,
+  ) -> Option<&'a T> {
+    loop {
+      match Self::locate(node, cmp) {
+        Ok(index) => return Some(&node.keys[index]),
+        Err(_) if node.is_leaf() => return None,
+        Err(index) => node = &node.children[index],
+      }
+    }
+  }
+  pub(crate) fn search_node_mut<'a, T, F: Fn(&T) -> Ordering>(
+    &self,
+    mut node: &'a mut Node<T>,
+    cmp: &F,
+  ) -> Option<&'a mut T> {
+    loop {
+      match Self::locate(node, cmp) {
+        Ok(index) => return Some(&mut node.keys[index]),
+        Err(_) if node.is_leaf() => return None,
+        Err(index) => node = &mut node.children[index],
+      }
+    }
+  }
+  // 删除并返回关键字，根节点的关键字被合并到孩子中后，树的高度减一
+  pub(crate) fn remove_root<T, F: Fn(&T) -> Ordering>(
+    &self,
+    root: &mut Node<T>,
+    cmp: &F,
+  ) -> Option<T> {
+    let removed = self.remove_from(root, cmp);
+    if root.keys.is_empty() && !root.is_leaf() {
+      *root = root.children.pop().unwrap();
+    }
+    removed
+  }
+  // 从以 node 为根的子树中删除关键字，调用前保证 node 为根或至少有 t 个关键字，
+  // 下降前预先合并或借用，使得删除后不需要回溯调整
+  fn remove_from<T, F: Fn(&T) -> Ordering>(&self, node: &mut Node<T>, cmp: &F) -> Option<T> {
+    match Self::locate(node, cmp) {
+      // 情况1：关键字在叶节点中，直接删除
+      Ok(index) if node.is_leaf() => Some(node.keys.remove(index)),
+      Ok(index) => {
+        if node.children[index].keys.len() > self.min_keys_index {
+          // 情况2a：左孩子至少有t个关键字，用前驱替换
+          let predecessor = self.pop_max(&mut node.children[index]);
+          Some(mem::replace(&mut node.keys[index], predecessor))
+        } else if node.children[index + 1].keys.len() > self.min_keys_index {
+          // 情况2b：右孩子至少有t个关键字，用后继替换
+          let successor = self.pop_min(&mut node.children[index + 1]);
+          Some(mem::replace(&mut node.keys[index], successor))
+        } else {
+          // 情况2c：左右孩子都只有t-1个关键字，合并后在合并的节点中删除
+          self.merge_children(node, index);
+          self.remove_from(&mut node.children[index], cmp)
+        }
+      }
+      Err(_) if node.is_leaf() => None,
+      Err(index) => {
+        // 情况3：关键字不在当前节点，保证要下降的孩子至少有t个关键字
+        let index = self.fill_child(node, index);
+        self.remove_from(&mut node.children[index], cmp)
+      }
     }
   }
-  fn traverse_node<T: Ord + Debug>(node: &Node<T>, depth: usize) {
+  // 删除并返回子树中的最大关键字
+  fn pop_max<T>(&self, node: &mut Node<T>) -> T {
     if node.is_leaf() {
-      print!("{0:{<1$}{2:?}{0:}<1$} ", "", depth, node.keys);
+      return node.keys.pop().unwrap();
+    }
+    let index = self.fill_child(node, node.keys.len());
+    self.pop_max(&mut node.children[index])
+  }
+  // 删除并返回子树中的最小关键字
+  fn pop_min<T>(&self, node: &mut Node<T>) -> T {
+    if node.is_leaf() {
+      return node.keys.remove(0);
+    }
+    let index = self.fill_child(node, 0);
+    self.pop_min(&mut node.children[index])
+  }
+  // 保证第index个孩子至少有t个关键字，优先从兄弟节点借，否则与兄弟合并，返回该孩子合并后的位置
+  fn fill_child<T>(&self, node: &mut Node<T>, index: usize) -> usize {
+    if node.children[index].keys.len() > self.min_keys_index {
+      index
+    } else if index > 0 && node.children[index - 1].keys.len() > self.min_keys_index {
+      Self::borrow_from_prev(node, index);
+      index
+    } else if index < node.keys.len() && node.children[index + 1].keys.len() > self.min_keys_index {
+      Self::borrow_from_next(node, index);
+      index
+    } else if index < node.keys.len() {
+      self.merge_children(node, index);
+      index
     } else {
-      let _depth = depth + 1;
-      for (index, key) in node.keys.iter().enumerate() {
-        Self::traverse_node(&node.children[index], _depth);
-        print!("{0:{<1$}{2:?}{0:}<1$} ", "", depth, key);
+      self.merge_children(node, index - 1);
+      index - 1
+    }
+  }
+  // 左兄弟的最大关键字上移到父节点，父节点的分隔关键字下移到孩子的最左边
+  fn borrow_from_prev<T>(node: &mut Node<T>, index: usize) {
+    let (left, right) = node.children.split_at_mut(index);
+    let (sibling, child) = (left.last_mut().unwrap(), &mut right[0]);
+    let key = mem::replace(&mut node.keys[index - 1], sibling.keys.pop().unwrap());
+    child.keys.insert(0, key);
+    if let Some(grandchild) = sibling.children.pop() {
+      child.children.insert(0, grandchild);
+    }
+  }
+  // 右兄弟的最小关键字上移到父节点，父节点的分隔关键字下移到孩子的最右边
+  fn borrow_from_next<T>(node: &mut Node<T>, index: usize) {
+    let (left, right) = node.children.split_at_mut(index + 1);
+    let (child, sibling) = (&mut left[index], &mut right[0]);
+    let key = mem::replace(&mut node.keys[index], sibling.keys.remove(0));
+    child.keys.push(key);
+    if !sibling.is_leaf() {
+      child.children.push(sibling.children.remove(0));
+    }
+  }
+  // 将第index+1个孩子与分隔关键字一起并入第index个孩子
+  fn merge_children<T>(&self, node: &mut Node<T>, index: usize) {
+    let right = node.children.remove(index + 1);
+    let key = node.keys.remove(index);
+    let child = &mut node.children[index];
+    child.keys.push(key);
+    child.keys.extend(right.keys);
+    child.children.extend(right.children);
+    debug_assert!(child.keys.len() <= self.max_keys);
+  }
+}
+
+// 中序输出关键字，叶子节点整体输出，深度越大两侧的括号越多
+fn traverse_node<T: Debug>(node: &Node<T>, depth: usize, out: &mut String) {
+  if node.is_leaf() {
+    write!(out, "{0:{<1$}{2:?}{0:}<1$} ", "", depth, node.keys).unwrap();
+  } else {
+    let _depth = depth + 1;
+    for (index, key) in node.keys.iter().enumerate() {
+      traverse_node(&node.children[index], _depth, out);
+      write!(out, "{0:{<1$}{2:?}{0:}<1$} ", "", depth, key).unwrap();
+    }
+    traverse_node(node.children.last().unwrap(), _depth, out);
+  }
+}
+
+// 把 m 个单位尽量均匀地分成若干组，每组的单位数接近 target 且在 [t, 2t] 之间，
+// 只有一组时不受下限约束。节点的单位数为关键字数加一，叶子之间各隔一个上移到父层的关键字
+fn group_sizes(m: usize, target: usize, t: usize) -> impl Iterator<Item = usize> {
+  let groups = m.div_ceil(target).clamp(m.div_ceil(2 * t), (m / t).max(1));
+  (0..groups).map(move |i| m / groups + usize::from(i < m % groups))
+}
+
+// 先按 leaf_keys 切分出所有叶子，再逐层向上把孩子打包成尽量满的内部节点
+fn build_tree<T>(keys: Vec<T>, t: usize, leaf_keys: usize) -> Node<T> {
+  let degree = 2 * t;
+  let mut level = Vec::new();
+  let mut separators = Vec::new();
+  let mut keys = keys.into_iter();
+  let sizes: Vec<_> = group_sizes(keys.len() + 1, leaf_keys + 1, t).collect();
+  for (index, &size) in sizes.iter().enumerate() {
+    let leaf_keys = keys.by_ref().take(size - 1).collect();
+    level.push(Node::new(degree, Some(leaf_keys), None));
+    if index + 1 < sizes.len() {
+      separators.extend(keys.next());
+    }
+  }
+  while level.len() > 1 {
+    let mut children = level.into_iter();
+    let mut keys = separators.into_iter();
+    let sizes: Vec<_> = group_sizes(children.len(), degree, t).collect();
+    level = Vec::with_capacity(sizes.len());
+    separators = Vec::with_capacity(sizes.len() - 1);
+    for (index, &size) in sizes.iter().enumerate() {
+      let node_children = children.by_ref().take(size).collect();
+      let node_keys = keys.by_ref().take(size - 1).collect();
+      level.push(Node::new(degree, Some(node_keys), Some(node_children)));
+      if index + 1 < sizes.len() {
+        separators.extend(keys.next());
       }
-      Self::traverse_node(node.children.last().unwrap(), _depth);
     }
   }
+  level.pop().unwrap()
 }
 
+// S 为节点内的查找策略，默认使用二分查找
 #[derive(Debug)]
-pub struct BTree<T> {
+pub struct BTree<T, S = Binary> {
   root: Node<T>,
-  props: BTreeProps,
+  props: BTreeProps<S>,
+  length: usize,
 }
 
-impl<T> BTree<T>
-where
-  T: Ord + Copy + Default + Debug,
-{
+impl<T: Ord> BTree<T> {
   pub fn new(t: usize) -> Self {
+    Self::with_strategy(t)
+  }
+
+  // 由非降序序列自底向上以线性时间构建，与 insert 一样保留重复元素，遇到降序元素时返回其下标。
+  // fill_factor 为叶子的填充率，取值 (0, 1]，实际关键字数不少于 t-1；内部节点尽量填满
+  pub fn bulk_load<I: IntoIterator<Item = T>>(
+    iter: I,
+    t: usize,
+    fill_factor: f64,
+  ) -> Result<Self, BulkLoadError> {
+    if t < 2 {
+      return Err(BulkLoadError::InvalidDegree { t });
+    }
+    // 写成取反的形式，NaN 也会被拒绝
+    if !(fill_factor > 0.0 && fill_factor <= 1.0) {
+      return Err(BulkLoadError::InvalidFillFactor { fill_factor });
+    }
+    let mut keys: Vec<T> = Vec::new();
+    for (index, key) in iter.into_iter().enumerate() {
+      if keys.last().is_some_and(|last| *last > key) {
+        return Err(OutOfOrder { index }.into());
+      }
+      keys.push(key);
+    }
+    let mut b_tree = Self::new(t);
+    let leaf_keys = ((fill_factor * b_tree.props.max_keys as f64).round() as usize)
+      .clamp(t - 1, b_tree.props.max_keys);
+    b_tree.length = keys.len();
+    b_tree.root = build_tree(keys, t, leaf_keys);
+    Ok(b_tree)
+  }
+}
+
+impl<T: Ord, S: SearchStrategy> BTree<T, S> {
+  // 使用指定的节点内查找策略，例如 BTree::<u32, Branchless>::with_strategy(64)
+  pub fn with_strategy(t: usize) -> Self {
     let degree = 2 * t; // t为最小度数，B树的阶数为2t即偶数阶，方便插入或删除时将节点分成两部分
     Self {
       root: Node::new(degree, None, None),
       props: BTreeProps::new(degree),
+      length: 0,
     }
   }
 
   pub fn insert(&mut self, key: T) {
-    // 如果根节点已满，则创建新根节点并分裂原根节点作为它的孩子
-    if self.props.is_maxed_out(&self.root) {
-      let mut new_root = Node::new(self.props.degree, None, None);
-      // 交换后new_root为原根节点，self.root为新根节点
-      mem::swap(&mut new_root, &mut self.root);
-      self.root.children.insert(0, new_root);
-      self.props.split_child(&mut self.root, 0);
-    }
-    // 插入关键字
-    self.props.insert_non_full(&mut self.root, key);
+    self.props.insert_root(&mut self.root, key);
+    self.length += 1;
   }
 
-  pub fn traverse(&self) {
-    BTreeProps::traverse_node(&self.root, 0);
-    println!();
+  // 删除关键字，关键字不存在时返回false
+  pub fn remove(&mut self, key: &T) -> bool {
+    let removed = self
+      .props
+      .remove_root(&mut self.root, &|k: &T| k.cmp(key))
+      .is_some();
+    if removed {
+      self.length -= 1;
+    }
+    removed
   }
 
   pub fn search(&self, key: T) -> bool {
-    let mut current_node = &self.root;
-    let mut index: usize;
+    self
+      .props
+      .search_node(&self.root, &|k: &T| k.cmp(&key))
+      .is_some()
+  }
+
+  // 按升序返回 range 内的关键字
+  pub fn range<Q, R>(&self, range: R) -> Range<'_, T>
+  where
+    T: Borrow<Q>,
+    Q: Ord + ?Sized,
+    R: RangeBounds<Q>,
+  {
+    Range {
+      raw: RawIter::new(&self.root, range.start_bound(), range.end_bound(), |k| {
+        k.borrow()
+      }),
+    }
+  }
+}
+
+impl<T, S> BTree<T, S> {
+  // 关键字个数，重复插入的关键字分别计数
+  pub fn len(&self) -> usize {
+    self.length
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.length == 0
+  }
+
+  pub fn iter(&self) -> Iter<'_, T> {
+    Iter {
+      raw: RawIter::full(&self.root),
+      remaining: self.length,
+    }
+  }
+
+  // 统计树高、节点数与节点的平均填充率
+  pub fn stats(&self) -> BTreeStats {
+    let mut stats = BTreeStats {
+      height: 0,
+      nodes: 0,
+      leaves: 0,
+      fill_factor: 0.0,
+    };
+    let mut level = vec![&self.root];
+    let mut keys = 0;
+    while !level.is_empty() {
+      stats.height += 1;
+      stats.nodes += level.len();
+      keys += level.iter().map(|node| node.keys.len()).sum::<usize>();
+      if level[0].is_leaf() {
+        stats.leaves += level.len();
+      }
+      level = level.iter().flat_map(|node| &node.children).collect();
+    }
+    stats.fill_factor = keys as f64 / (stats.nodes * self.props.max_keys) as f64;
+    stats
+  }
+
+  // 检查关键字顺序、每个节点的关键字个数、孩子个数、叶子深度以及 length 是否一致
+  pub fn validate(&self) -> Result<(), BTreeViolation>
+  where
+    T: Ord,
+  {
+    let mut state = Validation {
+      next_id: 0,
+      prev: None,
+      leaf_depth: None,
+      keys: 0,
+    };
+    validate_node(&self.root, &self.props, 0, &mut state)?;
+    if state.keys != self.length {
+      return Err(BTreeViolation::LengthMismatch {
+        length: self.length,
+        actual: state.keys,
+      });
+    }
+    Ok(())
+  }
+}
+
+impl<T: Debug, S> BTree<T, S> {
+  // 返回带括号装饰的中序遍历结果，括号层数表示节点深度
+  pub fn traverse(&self) -> String {
+    let mut out = String::new();
+    traverse_node(&self.root, 0, &mut out);
+    out
+  }
+
+  // 导出 Graphviz DOT 格式的节点结构，每个节点为一个 record，关键字之间的字段连向对应的孩子
+  pub fn to_dot(&self) -> String {
+    let mut out = String::from("digraph BTree {\n  node [shape=record];\n");
+    dot_node(&self.root, &mut 0, &mut out);
+    out.push_str("}\n");
+    out
+  }
+}
+
+// bulk_load 的参数不合法或输入序列乱序
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BulkLoadError {
+  // 最小度数小于 2
+  InvalidDegree { t: usize },
+  // 填充率不在 (0, 1] 内
+  InvalidFillFactor { fill_factor: f64 },
+  OutOfOrder(OutOfOrder),
+}
+
+impl From<OutOfOrder> for BulkLoadError {
+  fn from(e: OutOfOrder) -> Self {
+    BulkLoadError::OutOfOrder(e)
+  }
+}
+
+impl Display for BulkLoadError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      BulkLoadError::InvalidDegree { t } => {
+        write!(f, "minimum degree {} is less than 2", t)
+      }
+      BulkLoadError::InvalidFillFactor { fill_factor } => {
+        write!(f, "fill factor {} is not in (0, 1]", fill_factor)
+      }
+      BulkLoadError::OutOfOrder(e) => Display::fmt(e, f),
+    }
+  }
+}
+
+impl Error for BulkLoadError {
+  fn source(&self) -> Option<&(dyn Error + 'static)> {
+    match self {
+      BulkLoadError::OutOfOrder(e) => Some(e),
+      _ => None,
+    }
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BTreeStats {
+  // 层数，只有根节点时为 1
+  pub height: usize,
+  pub nodes: usize,
+  pub leaves: usize,
+  // 关键字总数与所有节点容量 (2t-1) 之和的比值
+  pub fill_factor: f64,
+}
+
+// validate 发现的不变式破坏，节点以其先序遍历序号 node 标识
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BTreeViolation {
+  // 节点的第 index 个关键字小于中序遍历中的前一个关键字
+  Unordered {
+    node: usize,
+    index: usize,
+  },
+  TooManyKeys {
+    node: usize,
+    keys: usize,
+    max: usize,
+  },
+  // 非根节点的关键字少于 t-1 个，或有孩子的根节点没有关键字
+  TooFewKeys {
+    node: usize,
+    keys: usize,
+    min: usize,
+  },
+  // 内部节点的孩子个数不等于关键字个数加一
+  ChildCountMismatch {
+    node: usize,
+    keys: usize,
+    children: usize,
+  },
+  // 叶子的深度与最左侧叶子不同
+  LeafDepthMismatch {
+    node: usize,
+    depth: usize,
+    expected: usize,
+  },
+  // length 与树中的关键字个数不一致
+  LengthMismatch {
+    length: usize,
+    actual: usize,
+  },
+}
+
+impl Display for BTreeViolation {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      BTreeViolation::Unordered { node, index } => write!(
+        f,
+        "key {} of node {} is less than its predecessor",
+        index, node
+      ),
+      BTreeViolation::TooManyKeys { node, keys, max } => {
+        write!(f, "node {} has {} keys, more than {}", node, keys, max)
+      }
+      BTreeViolation::TooFewKeys { node, keys, min } => {
+        write!(f, "node {} has {} keys, fewer than {}", node, keys, min)
+      }
+      BTreeViolation::ChildCountMismatch {
+        node,
+        keys,
+        children,
+      } => write!(
+        f,
+        "node {} has {} keys but {} children",
+        node, keys, children
+      ),
+      BTreeViolation::LeafDepthMismatch {
+        node,
+        depth,
+        expected,
+      } => write!(
+        f,
+        "leaf {} is at depth {} instead of {}",
+        node, depth, expected
+      ),
+      BTreeViolation::LengthMismatch { length, actual } => {
+        write!(f, "tree length is {} but has {} keys", length, actual)
+      }
+    }
+  }
+}
+
+impl Error for BTreeViolation {}
+
+struct Validation<'a, T> {
+  next_id: usize,
+  // 中序遍历的前一个关键字
+  prev: Option<&'a T>,
+  // 第一个叶子的深度，其余叶子都应与之相同
+  leaf_depth: Option<usize>,
+  keys: usize,
+}
+
+// 先序编号节点，中序检查关键字顺序
+fn validate_node<'a, T: Ord, S>(
+  node: &'a Node<T>,
+  props: &BTreeProps<S>,
+  depth: usize,
+  state: &mut Validation<'a, T>,
+) -> Result<(), BTreeViolation> {
+  let id = state.next_id;
+  state.next_id += 1;
+  let keys = node.keys.len();
+  if keys > props.max_keys {
+    return Err(BTreeViolation::TooManyKeys {
+      node: id,
+      keys,
+      max: props.max_keys,
+    });
+  }
+  // 根节点不受 t-1 的下限约束，但有孩子时至少要有一个关键字
+  let min = if depth > 0 {
+    props.min_keys_index
+  } else {
+    usize::from(!node.is_leaf())
+  };
+  if keys < min {
+    return Err(BTreeViolation::TooFewKeys {
+      node: id,
+      keys,
+      min,
+    });
+  }
+  if !node.is_leaf() && node.children.len() != keys + 1 {
+    return Err(BTreeViolation::ChildCountMismatch {
+      node: id,
+      keys,
+      children: node.children.len(),
+    });
+  }
+  if node.is_leaf() {
+    let expected = *state.leaf_depth.get_or_insert(depth);
+    if depth != expected {
+      return Err(BTreeViolation::LeafDepthMismatch {
+        node: id,
+        depth,
+        expected,
+      });
+    }
+  }
+  state.keys += keys;
+  for (index, key) in node.keys.iter().enumerate() {
+    if let Some(child) = node.children.get(index) {
+      validate_node(child, props, depth + 1, state)?;
+    }
+    if state.prev.is_some_and(|prev| key < prev) {
+      return Err(BTreeViolation::Unordered { node: id, index });
+    }
+    state.prev = Some(key);
+  }
+  if let Some(child) = node.children.last() {
+    validate_node(child, props, depth + 1, state)?;
+  }
+  Ok(())
+}
+
+// 输出 DOT 节点与边，节点编号为先序遍历序号，字段 <c{i}> 为第 i 个孩子的位置
+fn dot_node<T: Debug>(node: &Node<T>, next_id: &mut usize, out: &mut String) -> usize {
+  let id = *next_id;
+  *next_id += 1;
+  let mut fields = Vec::with_capacity(2 * node.keys.len() + 1);
+  for (index, key) in node.keys.iter().enumerate() {
+    fields.push(format!("<c{}>", index));
+    let mut label = String::new();
+    for c in format!("{:?}", key).chars() {
+      // record 标签中有特殊含义的字符需要转义
+      if "\\\"{}|<> ".contains(c) {
+        label.push('\\');
+      }
+      label.push(c);
+    }
+    fields.push(label);
+  }
+  fields.push(format!("<c{}>", node.keys.len()));
+  writeln!(out, "  n{} [label=\"{}\"];", id, fields.join("|")).unwrap();
+  for (index, child) in node.children.iter().enumerate() {
+    let child_id = dot_node(child, next_id, out);
+    writeln!(out, "  n{}:c{} -> n{};", id, index, child_id).unwrap();
+  }
+  id
+}
+
+// 中序遍历的双端迭代器，front 栈顶的 (node, i) 表示下一个元素为 node.keys[i]，
+// back 栈顶的 (node, i) 表示下一个元素为 node.keys[i - 1]，两端相遇时结束
+pub(crate) struct RawIter<'a, T> {
+  front: Vec<(&'a Node<T>, usize)>,
+  back: Vec<(&'a Node<T>, usize)>,
+}
+
+impl<'a, T> Clone for RawIter<'a, T> {
+  fn clone(&self) -> Self {
+    Self {
+      front: self.front.clone(),
+      back: self.back.clone(),
+    }
+  }
+}
+
+impl<'a, T> RawIter<'a, T> {
+  // 遍历整棵树，两端分别从最左与最右的叶子开始
+  pub(crate) fn full(root: &'a Node<T>) -> Self {
+    let mut iter = Self {
+      front: Vec::new(),
+      back: Vec::new(),
+    };
+    let mut node = root;
+    loop {
+      iter.front.push((node, 0));
+      match node.children.first() {
+        Some(child) => node = child,
+        None => break,
+      }
+    }
+    let mut node = root;
+    loop {
+      iter.back.push((node, node.keys.len()));
+      match node.children.last() {
+        Some(child) => node = child,
+        None => break,
+      }
+    }
+    iter.normalize();
+    iter
+  }
+
+  // key 取出元素中参与比较的部分，例如映射中键值对的键
+  pub(crate) fn new<Q, F>(root: &'a Node<T>, start: Bound<&Q>, end: Bound<&Q>, key: F) -> Self
+  where
+    Q: Ord + ?Sized,
+    F: Fn(&T) -> &Q,
+  {
+    let mut iter = Self {
+      front: Vec::new(),
+      back: Vec::new(),
+    };
+    // 从根向下寻找第一个不小于下界的位置
+    let mut node = root;
     loop {
-      index = current_node.keys.len();
-      while index >= 1 && current_node.keys[index - 1] > key {
-        index -= 1;
+      let index = match start {
+        Bound::Included(start) => node.keys.partition_point(|k| key(k) < start),
+        Bound::Excluded(start) => node.keys.partition_point(|k| key(k) <= start),
+        Bound::Unbounded => 0,
+      };
+      iter.front.push((node, index));
+      if node.is_leaf() {
+        break;
+      }
+      node = &node.children[index];
+    }
+    // 从根向下寻找最后一个不大于上界的位置
+    let mut node = root;
+    loop {
+      let index = match end {
+        Bound::Included(end) => node.keys.partition_point(|k| key(k) <= end),
+        Bound::Excluded(end) => node.keys.partition_point(|k| key(k) < end),
+        Bound::Unbounded => node.keys.len(),
+      };
+      iter.back.push((node, index));
+      if node.is_leaf() {
+        break;
+      }
+      node = &node.children[index];
+    }
+    iter.normalize();
+    // 下界之后的第一个元素已经在上界之外，范围为空
+    if let (Some(first), Some(last)) = (iter.peek_front(), iter.peek_back()) {
+      if key(first) > key(last) {
+        iter.clear();
       }
-      // 如果在当前节点找到关键字，返回true
-      if index >= 1 && current_node.keys[index - 1] == key {
-        break true;
-        // 如果比较完仍找不到，需要向下寻找
-      } else if current_node.is_leaf() {
-        break false;
-      } else {
-        current_node = &current_node.children[index];
+    }
+    iter
+  }
+
+  // 弹出已经遍历完的节点，使两个栈顶都指向下一个元素
+  fn normalize(&mut self) {
+    while let Some(&(node, index)) = self.front.last() {
+      if index < node.keys.len() {
+        break;
+      }
+      self.front.pop();
+    }
+    while let Some(&(_, 0)) = self.back.last() {
+      self.back.pop();
+    }
+  }
+
+  fn clear(&mut self) {
+    self.front.clear();
+    self.back.clear();
+  }
+
+  fn peek_front(&self) -> Option<&'a T> {
+    self.front.last().map(|&(node, index)| &node.keys[index])
+  }
+
+  fn peek_back(&self) -> Option<&'a T> {
+    self.back.last().map(|&(node, index)| &node.keys[index - 1])
+  }
+
+  pub(crate) fn next(&mut self) -> Option<&'a T> {
+    let item = self.peek_front()?;
+    let last = ptr::eq(item, self.peek_back()?);
+    let top = self.front.last_mut().unwrap();
+    top.1 += 1;
+    let (node, index) = *top;
+    // 进入右侧孩子的最左叶子
+    if !node.is_leaf() {
+      let mut child = &node.children[index];
+      loop {
+        self.front.push((child, 0));
+        if child.is_leaf() {
+          break;
+        }
+        child = &child.children[0];
+      }
+    }
+    self.normalize();
+    if last {
+      self.clear();
+    }
+    Some(item)
+  }
+
+  pub(crate) fn next_back(&mut self) -> Option<&'a T> {
+    let item = self.peek_back()?;
+    let last = ptr::eq(item, self.peek_front()?);
+    let top = self.back.last_mut().unwrap();
+    top.1 -= 1;
+    let (node, index) = *top;
+    // 进入左侧孩子的最右叶子
+    if !node.is_leaf() {
+      let mut child = &node.children[index];
+      loop {
+        self.back.push((child, child.keys.len()));
+        if child.is_leaf() {
+          break;
+        }
+        child = child.children.last().unwrap();
       }
     }
+    self.normalize();
+    if last {
+      self.clear();
+    }
+    Some(item)
+  }
+}
+
+pub struct Iter<'a, T> {
+  raw: RawIter<'a, T>,
+  remaining: usize,
+}
+
+impl<'a, T> Clone for Iter<'a, T> {
+  fn clone(&self) -> Self {
+    Self {
+      raw: self.raw.clone(),
+      remaining: self.remaining,
+    }
+  }
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+  type Item = &'a T;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    let item = self.raw.next()?;
+    self.remaining -= 1;
+    Some(item)
+  }
+
+  fn size_hint(&self) -> (usize, Option<usize>) {
+    (self.remaining, Some(self.remaining))
+  }
+}
+
+impl<'a, T> DoubleEndedIterator for Iter<'a, T> {
+  fn next_back(&mut self) -> Option<Self::Item> {
+    let item = self.raw.next_back()?;
+    self.remaining -= 1;
+    Some(item)
+  }
+}
+
+impl<'a, T> ExactSizeIterator for Iter<'a, T> {}
+
+impl<'a, T> FusedIterator for Iter<'a, T> {}
+
+pub struct Range<'a, T> {
+  raw: RawIter<'a, T>,
+}
+
+impl<'a, T> Clone for Range<'a, T> {
+  fn clone(&self) -> Self {
+    Self {
+      raw: self.raw.clone(),
+    }
+  }
+}
+
+impl<'a, T> Iterator for Range<'a, T> {
+  type Item = &'a T;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    self.raw.next()
+  }
+}
+
+impl<'a, T> DoubleEndedIterator for Range<'a, T> {
+  fn next_back(&mut self) -> Option<Self::Item> {
+    self.raw.next_back()
+  }
+}
+
+impl<'a, T> FusedIterator for Range<'a, T> {}
+
+pub struct IntoIter<T> {
+  inner: vec::IntoIter<T>,
+}
+
+impl<T> Iterator for IntoIter<T> {
+  type Item = T;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    self.inner.next()
+  }
+
+  fn size_hint(&self) -> (usize, Option<usize>) {
+    self.inner.size_hint()
+  }
+}
+
+impl<T> DoubleEndedIterator for IntoIter<T> {
+  fn next_back(&mut self) -> Option<Self::Item> {
+    self.inner.next_back()
+  }
+}
+
+impl<T> ExactSizeIterator for IntoIter<T> {}
+
+impl<T> FusedIterator for IntoIter<T> {}
+
+// 按中序将子树中的关键字移动到 out 中
+pub(crate) fn flatten<T>(node: Node<T>, out: &mut Vec<T>) {
+  let mut children = node.children.into_iter();
+  for key in node.keys {
+    if let Some(child) = children.next() {
+      flatten(child, out);
+    }
+    out.push(key);
+  }
+  if let Some(child) = children.next() {
+    flatten(child, out);
+  }
+}
+
+impl<T, S> IntoIterator for BTree<T, S> {
+  type Item = T;
+  type IntoIter = IntoIter<T>;
+
+  fn into_iter(self) -> Self::IntoIter {
+    let mut keys = Vec::with_capacity(self.length);
+    flatten(self.root, &mut keys);
+    IntoIter {
+      inner: keys.into_iter(),
+    }
+  }
+}
+
+impl<'a, T, S> IntoIterator for &'a BTree<T, S> {
+  type Item = &'a T;
+  type IntoIter = Iter<'a, T>;
+
+  fn into_iter(self) -> Self::IntoIter {
+    self.iter()
   }
 }
 
 #[cfg(test)]
 mod tests {
-  use super::BTree;
+  use super::{
+    BTree, BTreeStats, BTreeViolation, Binary, Branchless, BulkLoadError, Linear, Node, OutOfOrder,
+    SearchStrategy,
+  };
+  use std::{collections::BTreeSet, mem, ops::Bound, time::Instant};
+
+  // 检查节点关键字个数、孩子个数与叶子深度，返回子树的叶子深度并按中序收集关键字
+  fn check_node(node: &Node<i32>, b_tree: &BTree<i32>, is_root: bool, out: &mut Vec<i32>) -> usize {
+    assert!(node.keys.len() <= b_tree.props.max_keys);
+    if !is_root {
+      assert!(node.keys.len() >= b_tree.props.min_keys_index);
+    }
+    if node.is_leaf() {
+      out.extend(&node.keys);
+      return 0;
+    }
+    assert_eq!(node.children.len(), node.keys.len() + 1);
+    let depth = check_node(&node.children[0], b_tree, false, out);
+    for (key, child) in node.keys.iter().zip(&node.children[1..]) {
+      out.push(*key);
+      assert_eq!(check_node(child, b_tree, false, out), depth);
+    }
+    depth + 1
+  }
+
+  fn check(b_tree: &BTree<i32>, expected: &BTreeSet<i32>) {
+    let mut keys = Vec::new();
+    check_node(&b_tree.root, b_tree, true, &mut keys);
+    assert!(keys.iter().eq(expected.iter()));
+  }
+
   #[test]
   fn test_b_tree() {
     let mut b_tree = BTree::new(2);
@@ -159,9 +1112,280 @@ mod tests {
       b_tree.insert(val);
     }
     assert!(b_tree.search(30));
-    assert_eq!(b_tree.search(16), false);
+    assert!(!b_tree.search(16));
     // 可视化：https://www.cs.usfca.edu/~galles/visualization/BTree.html，选择偶数degree并勾选 Preemtive Split / Merge (Even max degree only)
     println!("B-Tree: {:?}", b_tree.root.children);
-    b_tree.traverse();
+    assert_eq!(
+      b_tree.traverse(),
+      "{{[5]}} {6} {{[7]}} 10 {{[11, 12, 15]}} {20} {{[30]}} "
+    );
+  }
+
+  #[test]
+  fn test_b_tree_bulk_load() {
+    for t in 2..6 {
+      for fill_factor in [0.1, 0.5, 0.75, 1.0] {
+        for n in [0, 1, 2, 3, 7, 8, 50, 1000] {
+          let mut b_tree = BTree::bulk_load(0..n, t, fill_factor).unwrap();
+          let mut expected: BTreeSet<_> = (0..n).collect();
+          check(&b_tree, &expected);
+          assert_eq!(b_tree.len(), n as usize);
+          // 构建后可以继续插入与删除
+          for key in (0..n + 20).step_by(3) {
+            if expected.remove(&key) {
+              assert!(b_tree.remove(&key));
+            } else {
+              b_tree.insert(key);
+              expected.insert(key);
+            }
+            check(&b_tree, &expected);
+          }
+        }
+      }
+    }
+    // 填充率为 1 时叶子几乎全满
+    let b_tree = BTree::bulk_load(0..10000, 4, 1.0).unwrap();
+    let mut leaves = vec![&b_tree.root];
+    while !leaves[0].is_leaf() {
+      leaves = leaves.iter().flat_map(|node| &node.children).collect();
+    }
+    // 每个叶子连同其后的分隔关键字占 8 个，叶子个数为最少的 ceil((n + 1) / 8)
+    assert_eq!(leaves.len(), 10001usize.div_ceil(8));
+    assert!(leaves.iter().all(|leaf| leaf.keys.len() >= 6));
+    let mut b_tree = BTree::bulk_load([1, 1, 2, 3, 3, 3, 3, 4], 2, 1.0).unwrap();
+    assert_eq!(b_tree.validate(), Ok(()));
+    assert_eq!(b_tree.len(), 8);
+    assert!(b_tree.iter().copied().eq([1, 1, 2, 3, 3, 3, 3, 4]));
+    assert!(b_tree.remove(&3));
+    assert!(b_tree.iter().copied().eq([1, 1, 2, 3, 3, 3, 4]));
+    assert_eq!(
+      BTree::bulk_load([1, 3, 2], 2, 1.0).unwrap_err(),
+      BulkLoadError::OutOfOrder(OutOfOrder { index: 2 })
+    );
+    assert_eq!(
+      BTree::bulk_load(0..10, 1, 1.0).unwrap_err(),
+      BulkLoadError::InvalidDegree { t: 1 }
+    );
+    for fill_factor in [0.0, 1.5, f64::NAN] {
+      assert!(matches!(
+        BTree::bulk_load(0..10, 2, fill_factor),
+        Err(BulkLoadError::InvalidFillFactor { .. })
+      ));
+    }
+  }
+
+  #[test]
+  fn test_b_tree_validate() {
+    let mut b_tree = BTree::new(2);
+    for val in [10, 20, 30, 5, 6, 7, 11, 12, 15] {
+      b_tree.insert(val);
+    }
+    assert_eq!(b_tree.validate(), Ok(()));
+    assert_eq!(
+      b_tree.stats(),
+      BTreeStats {
+        height: 3,
+        nodes: 7,
+        leaves: 4,
+        fill_factor: 9.0 / 21.0,
+      }
+    );
+    let dot = b_tree.to_dot();
+    assert!(
+      dot.starts_with("digraph BTree {\n  node [shape=record];\n  n0 [label=\"<c0>|10|<c1>\"];")
+    );
+    assert!(dot.contains("n5 [label=\"<c0>|11|<c1>|12|<c2>|15|<c3>\"];"));
+    assert!(dot.contains("n0:c1 -> n4;"));
+    assert!(dot.contains("n4:c0 -> n5;"));
+    let mut words = BTree::new(2);
+    words.insert("a|b".to_string());
+    assert!(words
+      .to_dot()
+      .contains(r#"n0 [label="<c0>|\"a\|b\"|<c1>"];"#));
+
+    b_tree.length = 8;
+    assert_eq!(
+      b_tree.validate(),
+      Err(BTreeViolation::LengthMismatch {
+        length: 8,
+        actual: 9
+      })
+    );
+    b_tree.length = 9;
+    b_tree.root.children[1].children[0].keys[0] = 25;
+    assert_eq!(
+      b_tree.validate(),
+      Err(BTreeViolation::Unordered { node: 5, index: 1 })
+    );
+    b_tree.root.children[1].children[0].keys[0] = 11;
+    let leaf = b_tree.root.children[0].children.pop().unwrap();
+    assert_eq!(
+      b_tree.validate(),
+      Err(BTreeViolation::ChildCountMismatch {
+        node: 1,
+        keys: 1,
+        children: 1
+      })
+    );
+    b_tree.root.children[0] = leaf;
+    // 第一个叶子决定期望的深度
+    assert_eq!(
+      b_tree.validate(),
+      Err(BTreeViolation::LeafDepthMismatch {
+        node: 3,
+        depth: 2,
+        expected: 1
+      })
+    );
+    assert_eq!(BTree::<i32>::new(2).stats().height, 1);
+
+    // 只有一个孩子、没有关键字的根节点
+    let mut b_tree = BTree::new(2);
+    b_tree.insert(1);
+    assert_eq!(b_tree.validate(), Ok(()));
+    let leaf = mem::replace(&mut b_tree.root, Node::new(4, None, None));
+    b_tree.root.children.push(leaf);
+    assert_eq!(
+      b_tree.validate(),
+      Err(BTreeViolation::TooFewKeys {
+        node: 0,
+        keys: 0,
+        min: 1
+      })
+    );
+  }
+
+  #[test]
+  fn test_b_tree_remove() {
+    for t in 2..5 {
+      let mut b_tree = BTree::new(t);
+      let mut expected = BTreeSet::new();
+      for i in 0..101 {
+        let key = (i * 37) % 101;
+        b_tree.insert(key);
+        expected.insert(key);
+        check(&b_tree, &expected);
+      }
+      assert!(!b_tree.remove(&1000));
+      for i in 0..250 {
+        let key = (i * 53) % 113;
+        assert_eq!(b_tree.remove(&key), expected.remove(&key));
+        check(&b_tree, &expected);
+        assert!(!b_tree.search(key));
+      }
+      // 删除全部关键字后根节点收缩为空叶子
+      assert!(expected.is_empty());
+      assert!(b_tree.root.is_leaf() && b_tree.root.keys.is_empty());
+      b_tree.insert(7);
+      assert!(b_tree.search(7));
+    }
+  }
+
+  #[test]
+  fn test_b_tree_owned_keys() {
+    let mut b_tree = BTree::new(2);
+    let words = [
+      "pear", "apple", "fig", "kiwi", "plum", "date", "lime", "grape",
+    ];
+    for word in words {
+      b_tree.insert(word.to_string());
+    }
+    assert!(words.iter().all(|word| b_tree.search(word.to_string())));
+    assert!(b_tree.remove(&"fig".to_string()));
+    assert!(!b_tree.search("fig".to_string()));
+    let mut b_tree = BTree::new(3);
+    (0..50u8).for_each(|i| b_tree.insert(vec![i; 3]));
+    assert!(b_tree.remove(&vec![7; 3]));
+    assert!(b_tree.search(vec![8; 3]));
+  }
+
+  #[test]
+  fn test_b_tree_iter() {
+    for t in [2, 3, 16] {
+      let mut b_tree = BTree::new(t);
+      let keys: Vec<i32> = (0..300).map(|i| (i * 89) % 300 * 2).collect();
+      keys.iter().for_each(|&key| b_tree.insert(key));
+      (0..300)
+        .step_by(3)
+        .for_each(|i| assert!(b_tree.remove(&(i * 2))));
+      let expected: BTreeSet<i32> = keys.iter().copied().filter(|k| k % 6 != 0).collect();
+      assert_eq!(b_tree.len(), expected.len());
+      assert!(b_tree.iter().eq(expected.iter()));
+      assert!(b_tree.iter().rev().eq(expected.iter().rev()));
+      assert_eq!(b_tree.iter().len(), expected.len());
+      // 两端交替前进直到相遇
+      let mut iter = b_tree.iter();
+      let mut expected_iter = expected.iter();
+      while let Some(key) = iter.next() {
+        assert_eq!(Some(key), expected_iter.next());
+        assert_eq!(iter.next_back(), expected_iter.next_back());
+      }
+      assert_eq!(expected_iter.next(), None);
+
+      let ranges = [
+        (Bound::Included(100), Bound::Excluded(200)),
+        (Bound::Excluded(101), Bound::Included(103)),
+        (Bound::Excluded(102), Bound::Included(103)),
+        (Bound::Unbounded, Bound::Included(3)),
+        (Bound::Included(590), Bound::Unbounded),
+        (Bound::Included(200), Bound::Excluded(100)),
+        (Bound::Unbounded, Bound::Unbounded),
+      ];
+      for range in ranges {
+        if let (Bound::Included(a), Bound::Excluded(b)) = range {
+          if a > b {
+            // BTreeSet::range 在下界大于上界时会 panic，这里直接期望为空
+            assert_eq!(b_tree.range(range).next(), None);
+            continue;
+          }
+        }
+        assert!(b_tree.range(range).eq(expected.range(range)));
+        assert!(b_tree.range(range).rev().eq(expected.range(range).rev()));
+      }
+      assert!(b_tree.into_iter().eq(expected.into_iter()));
+    }
+    let empty: BTree<i32> = BTree::new(2);
+    assert!(empty.is_empty());
+    assert_eq!(empty.iter().next(), None);
+    assert_eq!(empty.range(1..).next_back(), None);
+  }
+
+  fn check_strategy<S: SearchStrategy>() {
+    let mut b_tree = BTree::<u32, S>::with_strategy(4);
+    (0..500).for_each(|i| b_tree.insert((i * 7) % 500));
+    assert!((0..500).all(|i| b_tree.search(i)));
+    assert!(!b_tree.search(500));
+    assert!((0..500).step_by(2).all(|i| b_tree.remove(&i)));
+    assert!(b_tree.iter().copied().eq((1..500).step_by(2)));
+  }
+
+  #[test]
+  fn test_search_strategy() {
+    check_strategy::<Linear>();
+    check_strategy::<Binary>();
+    check_strategy::<Branchless>();
+  }
+
+  fn bench_strategy<S: SearchStrategy>(t: usize, keys: &[u64]) -> String {
+    let mut b_tree = BTree::<u64, S>::with_strategy(t);
+    let start = Instant::now();
+    keys.iter().for_each(|&key| b_tree.insert(key));
+    let insert_time = start.elapsed();
+    let start = Instant::now();
+    assert!(keys.iter().all(|&key| b_tree.search(key)));
+    format!("insert {:?}, search {:?}", insert_time, start.elapsed())
+  }
+
+  // cargo test --release -- --ignored --nocapture bench_search_strategy
+  #[test]
+  #[ignore]
+  fn bench_search_strategy() {
+    let keys: Vec<u64> = (0..1_000_000u64).map(|i| i * 7_919 % 1_000_003).collect();
+    for t in [2, 8, 32, 64, 128] {
+      println!("t = {t}");
+      println!("  linear:     {}", bench_strategy::<Linear>(t, &keys));
+      println!("  binary:     {}", bench_strategy::<Binary>(t, &keys));
+      println!("  branchless: {}", bench_strategy::<Branchless>(t, &keys));
+    }
   }
 }
@@ -0,0 +1,592 @@
+use std::{
+  borrow::Borrow, cmp::Ordering, iter::FusedIterator, marker::PhantomData, mem, ops::RangeBounds,
+  ptr::NonNull, vec,
+};
+
+use crate::b_tree::{self, BTreeProps, Node, RawIter};
+
+// 节点中的键值对，只按键比较，使 B 树的插入与分裂逻辑可以直接复用
+#[derive(Debug)]
+pub(crate) struct KeyValue<K, V> {
+  pub(crate) key: K,
+  pub(crate) value: V,
+}
+
+impl<K: Ord, V> PartialEq for KeyValue<K, V> {
+  fn eq(&self, other: &Self) -> bool {
+    self.key == other.key
+  }
+}
+
+impl<K: Ord, V> Eq for KeyValue<K, V> {}
+
+impl<K: Ord, V> PartialOrd for KeyValue<K, V> {
+  fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+    Some(self.cmp(other))
+  }
+}
+
+impl<K: Ord, V> Ord for KeyValue<K, V> {
+  fn cmp(&self, other: &Self) -> Ordering {
+    self.key.cmp(&other.key)
+  }
+}
+
+// 基于 B 树的有序映射，键值对作为关键字存放在 b_tree::Node 中，
+// t 为最小度数，与 BTree::new(t) 相同
+#[derive(Debug)]
+pub struct BTreeMap<K, V> {
+  root: Node<KeyValue<K, V>>,
+  props: BTreeProps,
+  length: usize,
+}
+
+// 按键比较节点中的键值对与目标键
+fn by_key<K: Borrow<Q>, V, Q: Ord + ?Sized>(key: &Q) -> impl Fn(&KeyValue<K, V>) -> Ordering + '_ {
+  move |entry| entry.key.borrow().cmp(key)
+}
+
+impl<K: Ord, V> BTreeMap<K, V> {
+  pub fn new(t: usize) -> Self {
+    let degree = 2 * t;
+    Self {
+      root: Node::new(degree, None, None),
+      props: BTreeProps::new(degree),
+      length: 0,
+    }
+  }
+
+  // 插入键值对，键已存在时替换值并返回旧值，原有的键保持不变
+  pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+    match self
+      .props
+      .insert_unique_root(&mut self.root, KeyValue { key, value })
+    {
+      Ok(_) => {
+        self.length += 1;
+        None
+      }
+      Err((entry, new)) => Some(mem::replace(&mut entry.value, new.value)),
+    }
+  }
+
+  pub fn get<Q>(&self, key: &Q) -> Option<&V>
+  where
+    K: Borrow<Q>,
+    Q: Ord + ?Sized,
+  {
+    self.get_key_value(key).map(|(_, value)| value)
+  }
+
+  pub fn get_key_value<Q>(&self, key: &Q) -> Option<(&K, &V)>
+  where
+    K: Borrow<Q>,
+    Q: Ord + ?Sized,
+  {
+    self
+      .props
+      .search_node(&self.root, &by_key(key))
+      .map(|entry| (&entry.key, &entry.value))
+  }
+
+  pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+  where
+    K: Borrow<Q>,
+    Q: Ord + ?Sized,
+  {
+    self
+      .props
+      .search_node_mut(&mut self.root, &by_key(key))
+      .map(|entry| &mut entry.value)
+  }
+
+  pub fn contains_key<Q>(&self, key: &Q) -> bool
+  where
+    K: Borrow<Q>,
+    Q: Ord + ?Sized,
+  {
+    self.get(key).is_some()
+  }
+
+  pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+  where
+    K: Borrow<Q>,
+    Q: Ord + ?Sized,
+  {
+    self.remove_entry(key).map(|(_, value)| value)
+  }
+
+  pub fn remove_entry<Q>(&mut self, key: &Q) -> Option<(K, V)>
+  where
+    K: Borrow<Q>,
+    Q: Ord + ?Sized,
+  {
+    let removed = self.props.remove_root(&mut self.root, &by_key(key))?;
+    self.length -= 1;
+    Some((removed.key, removed.value))
+  }
+
+  // 获取键对应的条目，用于原地查找并修改或插入
+  pub fn entry(&mut self, key: K) -> Entry<'_, K, V> {
+    let map = NonNull::from(self);
+    // SAFETY: map 来自 self 的独占借用，之后对映射的所有访问都经由这个指针派生
+    let BTreeMap { root, props, .. } = unsafe { &mut *map.as_ptr() };
+    let found = props
+      .search_node_mut(root, &by_key(&key))
+      .map(NonNull::from);
+    match found {
+      Some(entry) => Entry::Occupied(OccupiedEntry {
+        key,
+        entry,
+        map,
+        marker: PhantomData,
+      }),
+      None => Entry::Vacant(VacantEntry {
+        key,
+        // SAFETY: 查找时的借用已经结束，重新得到 self 的独占借用
+        map: unsafe { &mut *map.as_ptr() },
+      }),
+    }
+  }
+
+  // 按键的升序返回 range 内的键值对
+  pub fn range<Q, R>(&self, range: R) -> Range<'_, K, V>
+  where
+    K: Borrow<Q>,
+    Q: Ord + ?Sized,
+    R: RangeBounds<Q>,
+  {
+    Range {
+      raw: RawIter::new(
+        &self.root,
+        range.start_bound(),
+        range.end_bound(),
+        |entry| entry.key.borrow(),
+      ),
+    }
+  }
+
+  pub fn clear(&mut self) {
+    self.root = Node::new(self.props.degree, None, None);
+    self.length = 0;
+  }
+
+  pub fn len(&self) -> usize {
+    self.length
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.length == 0
+  }
+}
+
+impl<K, V> BTreeMap<K, V> {
+  // 按键的升序遍历键值对
+  pub fn iter(&self) -> Iter<'_, K, V> {
+    Iter {
+      raw: RawIter::full(&self.root),
+      remaining: self.length,
+    }
+  }
+
+  pub fn keys(&self) -> Keys<'_, K, V> {
+    Keys { inner: self.iter() }
+  }
+
+  pub fn values(&self) -> Values<'_, K, V> {
+    Values { inner: self.iter() }
+  }
+}
+
+pub enum Entry<'a, K: Ord, V> {
+  Vacant(VacantEntry<'a, K, V>),
+  Occupied(OccupiedEntry<'a, K, V>),
+}
+
+pub struct VacantEntry<'a, K: Ord, V> {
+  key: K,
+  map: &'a mut BTreeMap<K, V>,
+}
+
+pub struct OccupiedEntry<'a, K: Ord, V> {
+  // 调用者传入的键，remove_entry 用它删除键值对
+  key: K,
+  entry: NonNull<KeyValue<K, V>>,
+  map: NonNull<BTreeMap<K, V>>,
+  marker: PhantomData<&'a mut BTreeMap<K, V>>,
+}
+
+impl<'a, K: Ord, V> Entry<'a, K, V> {
+  pub fn key(&self) -> &K {
+    match self {
+      Entry::Vacant(entry) => entry.key(),
+      Entry::Occupied(entry) => entry.key(),
+    }
+  }
+
+  pub fn or_insert(self, default: V) -> &'a mut V {
+    match self {
+      Entry::Vacant(entry) => entry.insert(default),
+      Entry::Occupied(entry) => entry.into_mut(),
+    }
+  }
+
+  pub fn or_insert_with<F: FnOnce() -> V>(self, default: F) -> &'a mut V {
+    match self {
+      Entry::Vacant(entry) => entry.insert(default()),
+      Entry::Occupied(entry) => entry.into_mut(),
+    }
+  }
+
+  pub fn or_insert_with_key<F: FnOnce(&K) -> V>(self, default: F) -> &'a mut V {
+    match self {
+      Entry::Vacant(entry) => {
+        let value = default(&entry.key);
+        entry.insert(value)
+      }
+      Entry::Occupied(entry) => entry.into_mut(),
+    }
+  }
+
+  pub fn and_modify<F: FnOnce(&mut V)>(self, f: F) -> Self {
+    match self {
+      Entry::Vacant(entry) => Entry::Vacant(entry),
+      Entry::Occupied(mut entry) => {
+        f(entry.get_mut());
+        Entry::Occupied(entry)
+      }
+    }
+  }
+}
+
+impl<'a, K: Ord, V: Default> Entry<'a, K, V> {
+  pub fn or_default(self) -> &'a mut V {
+    self.or_insert_with(V::default)
+  }
+}
+
+impl<'a, K: Ord, V> VacantEntry<'a, K, V> {
+  pub fn key(&self) -> &K {
+    &self.key
+  }
+
+  pub fn into_key(self) -> K {
+    self.key
+  }
+
+  pub fn insert(self, value: V) -> &'a mut V {
+    let map = self.map;
+    let entry = KeyValue {
+      key: self.key,
+      value,
+    };
+    map.length += 1;
+    &mut map.props.insert_root(&mut map.root, entry).value
+  }
+}
+
+impl<'a, K: Ord, V> OccupiedEntry<'a, K, V> {
+  // 映射中原有的键
+  pub fn key(&self) -> &K {
+    // SAFETY: 在 entry 的生命周期内映射只能通过 entry 访问，键值对不会移动
+    unsafe { &self.entry.as_ref().key }
+  }
+
+  pub fn get(&self) -> &V {
+    // SAFETY: 同 key
+    unsafe { &self.entry.as_ref().value }
+  }
+
+  pub fn get_mut(&mut self) -> &mut V {
+    // SAFETY: 同 key
+    unsafe { &mut self.entry.as_mut().value }
+  }
+
+  pub fn into_mut(mut self) -> &'a mut V {
+    // SAFETY: 同 key，entry 被消耗后返回的引用独占映射直到 'a 结束
+    unsafe { &mut self.entry.as_mut().value }
+  }
+
+  pub fn insert(&mut self, value: V) -> V {
+    mem::replace(self.get_mut(), value)
+  }
+
+  pub fn remove(self) -> V {
+    self.remove_entry().1
+  }
+
+  pub fn remove_entry(self) -> (K, V) {
+    // SAFETY: entry 在此之后不再使用，map 仍然代表 entry 创建时对映射的独占借用
+    let map = unsafe { &mut *self.map.as_ptr() };
+    map.remove_entry(&self.key).unwrap()
+  }
+}
+
+pub struct Iter<'a, K, V> {
+  raw: RawIter<'a, KeyValue<K, V>>,
+  remaining: usize,
+}
+
+impl<'a, K, V> Clone for Iter<'a, K, V> {
+  fn clone(&self) -> Self {
+    Self {
+      raw: self.raw.clone(),
+      remaining: self.remaining,
+    }
+  }
+}
+
+impl<'a, K, V> Iterator for Iter<'a, K, V> {
+  type Item = (&'a K, &'a V);
+
+  fn next(&mut self) -> Option<Self::Item> {
+    let entry = self.raw.next()?;
+    self.remaining -= 1;
+    Some((&entry.key, &entry.value))
+  }
+
+  fn size_hint(&self) -> (usize, Option<usize>) {
+    (self.remaining, Some(self.remaining))
+  }
+}
+
+impl<'a, K, V> DoubleEndedIterator for Iter<'a, K, V> {
+  fn next_back(&mut self) -> Option<Self::Item> {
+    let entry = self.raw.next_back()?;
+    self.remaining -= 1;
+    Some((&entry.key, &entry.value))
+  }
+}
+
+impl<'a, K, V> ExactSizeIterator for Iter<'a, K, V> {}
+
+impl<'a, K, V> FusedIterator for Iter<'a, K, V> {}
+
+pub struct Range<'a, K, V> {
+  raw: RawIter<'a, KeyValue<K, V>>,
+}
+
+impl<'a, K, V> Clone for Range<'a, K, V> {
+  fn clone(&self) -> Self {
+    Self {
+      raw: self.raw.clone(),
+    }
+  }
+}
+
+impl<'a, K, V> Iterator for Range<'a, K, V> {
+  type Item = (&'a K, &'a V);
+
+  fn next(&mut self) -> Option<Self::Item> {
+    self.raw.next().map(|entry| (&entry.key, &entry.value))
+  }
+}
+
+impl<'a, K, V> DoubleEndedIterator for Range<'a, K, V> {
+  fn next_back(&mut self) -> Option<Self::Item> {
+    self.raw.next_back().map(|entry| (&entry.key, &entry.value))
+  }
+}
+
+impl<'a, K, V> FusedIterator for Range<'a, K, V> {}
+
+pub struct Keys<'a, K, V> {
+  inner: Iter<'a, K, V>,
+}
+
+impl<'a, K, V> Clone for Keys<'a, K, V> {
+  fn clone(&self) -> Self {
+    Self {
+      inner: self.inner.clone(),
+    }
+  }
+}
+
+impl<'a, K, V> Iterator for Keys<'a, K, V> {
+  type Item = &'a K;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    self.inner.next().map(|(key, _)| key)
+  }
+
+  fn size_hint(&self) -> (usize, Option<usize>) {
+    self.inner.size_hint()
+  }
+}
+
+impl<'a, K, V> DoubleEndedIterator for Keys<'a, K, V> {
+  fn next_back(&mut self) -> Option<Self::Item> {
+    self.inner.next_back().map(|(key, _)| key)
+  }
+}
+
+impl<'a, K, V> ExactSizeIterator for Keys<'a, K, V> {}
+
+impl<'a, K, V> FusedIterator for Keys<'a, K, V> {}
+
+pub struct Values<'a, K, V> {
+  inner: Iter<'a, K, V>,
+}
+
+impl<'a, K, V> Clone for Values<'a, K, V> {
+  fn clone(&self) -> Self {
+    Self {
+      inner: self.inner.clone(),
+    }
+  }
+}
+
+impl<'a, K, V> Iterator for Values<'a, K, V> {
+  type Item = &'a V;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    self.inner.next().map(|(_, value)| value)
+  }
+
+  fn size_hint(&self) -> (usize, Option<usize>) {
+    self.inner.size_hint()
+  }
+}
+
+impl<'a, K, V> DoubleEndedIterator for Values<'a, K, V> {
+  fn next_back(&mut self) -> Option<Self::Item> {
+    self.inner.next_back().map(|(_, value)| value)
+  }
+}
+
+impl<'a, K, V> ExactSizeIterator for Values<'a, K, V> {}
+
+impl<'a, K, V> FusedIterator for Values<'a, K, V> {}
+
+pub struct IntoIter<K, V> {
+  inner: vec::IntoIter<KeyValue<K, V>>,
+}
+
+impl<K, V> Iterator for IntoIter<K, V> {
+  type Item = (K, V);
+
+  fn next(&mut self) -> Option<Self::Item> {
+    self.inner.next().map(|entry| (entry.key, entry.value))
+  }
+
+  fn size_hint(&self) -> (usize, Option<usize>) {
+    self.inner.size_hint()
+  }
+}
+
+impl<K, V> DoubleEndedIterator for IntoIter<K, V> {
+  fn next_back(&mut self) -> Option<Self::Item> {
+    self.inner.next_back().map(|entry| (entry.key, entry.value))
+  }
+}
+
+impl<K, V> ExactSizeIterator for IntoIter<K, V> {}
+
+impl<K, V> FusedIterator for IntoIter<K, V> {}
+
+impl<K, V> IntoIterator for BTreeMap<K, V> {
+  type Item = (K, V);
+  type IntoIter = IntoIter<K, V>;
+
+  fn into_iter(self) -> Self::IntoIter {
+    let mut entries = Vec::with_capacity(self.length);
+    b_tree::flatten(self.root, &mut entries);
+    IntoIter {
+      inner: entries.into_iter(),
+    }
+  }
+}
+
+impl<'a, K, V> IntoIterator for &'a BTreeMap<K, V> {
+  type Item = (&'a K, &'a V);
+  type IntoIter = Iter<'a, K, V>;
+
+  fn into_iter(self) -> Self::IntoIter {
+    self.iter()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{BTreeMap, Entry};
+  use std::{collections, ops::Bound};
+
+  #[test]
+  fn test_b_tree_map() {
+    let mut map = BTreeMap::new(2);
+    for (i, word) in ["d", "b", "a", "c", "f", "e", "g"].iter().enumerate() {
+      assert_eq!(map.insert(word.to_string(), i), None);
+    }
+    assert_eq!(map.len(), 7);
+    assert_eq!(map.insert("a".to_string(), 10), Some(2));
+    assert_eq!(map.len(), 7);
+    assert_eq!(map.get("a"), Some(&10));
+    *map.get_mut("b").unwrap() += 1;
+    assert_eq!(map.get_key_value("b"), Some((&"b".to_string(), &2)));
+    assert_eq!(map.remove("d"), Some(0));
+    assert_eq!(map.remove("d"), None);
+    assert!(!map.contains_key("d"));
+
+    // test entry
+    *map.entry("z".to_string()).or_insert(0) += 5;
+    *map.entry("z".to_string()).or_insert(0) += 5;
+    assert_eq!(map.get("z"), Some(&10));
+    map
+      .entry("a".to_string())
+      .and_modify(|v| *v = 0)
+      .or_default();
+    assert_eq!(map.get("a"), Some(&0));
+    if let Entry::Occupied(entry) = map.entry("z".to_string()) {
+      assert_eq!(entry.remove(), 10);
+    }
+    assert_eq!(map.len(), 6);
+
+    // 插入过程中多次分裂，entry 返回的引用指向插入后的位置
+    let mut counts = BTreeMap::new(3);
+    for i in 0..1000 {
+      *counts.entry(i % 137).or_insert(0) += 1;
+    }
+    assert_eq!(counts.len(), 137);
+    assert!((0..137).all(|i| counts.get(&i) == Some(if i < 41 { &8 } else { &7 })));
+    for i in (0..137).step_by(2) {
+      assert!(counts.remove(&i).is_some());
+    }
+    assert_eq!(counts.len(), 68);
+    assert!((0..137).all(|i| counts.contains_key(&i) == (i % 2 == 1)));
+    counts.clear();
+    assert!(counts.is_empty());
+  }
+
+  #[test]
+  fn test_b_tree_map_iter() {
+    let mut map = BTreeMap::new(2);
+    let mut expected = collections::BTreeMap::new();
+    // 重复插入已存在的键，途中的分裂不影响结果
+    for i in 0..500 {
+      let key = (i * 37) % 211;
+      assert_eq!(map.insert(key, i), expected.insert(key, i));
+    }
+    assert_eq!(map.len(), expected.len());
+    assert!(map.iter().eq(expected.iter()));
+    assert!(map.iter().rev().eq(expected.iter().rev()));
+    assert_eq!(map.iter().len(), 211);
+    assert!(map.keys().eq(expected.keys()));
+    assert!(map.values().rev().eq(expected.values().rev()));
+    assert!(map.range(50..=60).eq(expected.range(50..=60)));
+    assert!(map.range(..20).rev().eq(expected.range(..20).rev()));
+    assert_eq!(map.range(300..).next(), None);
+    assert!((&map).into_iter().eq(&expected));
+    assert!(map.into_iter().eq(expected));
+
+    let mut words = BTreeMap::new(3);
+    for word in ["pear", "fig", "apple", "kiwi"] {
+      words.insert(word.to_string(), word.len());
+    }
+    assert_eq!(
+      words
+        .range::<str, _>((Bound::Included("b"), Bound::Excluded("l")))
+        .collect::<Vec<_>>(),
+      vec![(&"fig".to_string(), &3), (&"kiwi".to_string(), &4)]
+    );
+  }
+}
@@ -17,11 +17,9 @@ impl<T: Ord> BinarySearchTree<T> {
   }
 
   pub fn insert(&mut self, val: T) {
-    if self.val.is_none() {
-      self.val = Some(val);
-    } else {
-      match self.val.as_ref().unwrap().cmp(&val) {
-        Ordering::Equal => return,
+    if let Some(cur) = &self.val {
+      match cur.cmp(&val) {
+        Ordering::Equal => {}
         Ordering::Less => {
           if self.right.is_none() {
             self.right = Some(Box::new(BinarySearchTree::new()));
@@ -35,6 +33,8 @@ impl<T: Ord> BinarySearchTree<T> {
           self.left.as_mut().unwrap().insert(val);
         }
       }
+    } else {
+      self.val = Some(val);
     }
   }
 
@@ -100,10 +100,10 @@ mod tests {
     bst.insert(4);
     bst.insert(5);
 
-    assert_eq!(bst.search(1), true);
-    let res = bst.remove(3);
-    let res = bst.remove(1);
+    assert!(bst.search(1));
+    bst.remove(3);
+    bst.remove(1);
     println!("{:#?}", bst);
-    assert_eq!(bst.search(3), false);
+    assert!(!bst.search(3));
   }
 }
@@ -0,0 +1,180 @@
+// 快照与磁盘 B 树共用的二进制编码(小端)：
+// 整数使用 LEB128 变长编码，有符号整数先做 zigzag 变换，字符串与字节串带变长编码的长度前缀
+
+/// 可以编码为字节序列的值，用于快照与磁盘 B 树的键
+pub trait Codec: Sized {
+  fn encode(&self, out: &mut Vec<u8>);
+  // 从 input 头部解码一个值并前移 input，数据不完整或非法时返回 None
+  fn decode(input: &mut &[u8]) -> Option<Self>;
+}
+
+pub(crate) fn write_varint(out: &mut Vec<u8>, mut value: u64) {
+  while value >= 0x80 {
+    out.push(value as u8 | 0x80);
+    value >>= 7;
+  }
+  out.push(value as u8);
+}
+
+pub(crate) fn read_varint(input: &mut &[u8]) -> Option<u64> {
+  let mut value = 0u64;
+  for shift in (0..64).step_by(7) {
+    let (&byte, rest) = input.split_first()?;
+    *input = rest;
+    // 第 10 个字节只能提供最高 1 位
+    if shift == 63 && byte > 1 {
+      return None;
+    }
+    value |= u64::from(byte & 0x7f) << shift;
+    if byte & 0x80 == 0 {
+      return Some(value);
+    }
+  }
+  None
+}
+
+fn read_bytes<'a>(input: &mut &'a [u8]) -> Option<&'a [u8]> {
+  let len = usize::try_from(read_varint(input)?).ok()?;
+  if input.len() < len {
+    return None;
+  }
+  let (bytes, rest) = input.split_at(len);
+  *input = rest;
+  Some(bytes)
+}
+
+macro_rules! impl_unsigned {
+  ($($t:ty),*) => {$(
+    impl Codec for $t {
+      fn encode(&self, out: &mut Vec<u8>) {
+        write_varint(out, *self as u64);
+      }
+
+      fn decode(input: &mut &[u8]) -> Option<Self> {
+        <$t>::try_from(read_varint(input)?).ok()
+      }
+    }
+  )*};
+}
+
+macro_rules! impl_signed {
+  ($($t:ty),*) => {$(
+    impl Codec for $t {
+      fn encode(&self, out: &mut Vec<u8>) {
+        let value = *self as i64;
+        write_varint(out, ((value << 1) ^ (value >> 63)) as u64);
+      }
+
+      fn decode(input: &mut &[u8]) -> Option<Self> {
+        let value = read_varint(input)?;
+        <$t>::try_from((value >> 1) as i64 ^ -((value & 1) as i64)).ok()
+      }
+    }
+  )*};
+}
+
+impl_unsigned!(u8, u16, u32, u64, usize);
+impl_signed!(i8, i16, i32, i64, isize);
+
+impl Codec for bool {
+  fn encode(&self, out: &mut Vec<u8>) {
+    out.push(*self as u8);
+  }
+
+  fn decode(input: &mut &[u8]) -> Option<Self> {
+    match u8::decode(input)? {
+      0 => Some(false),
+      1 => Some(true),
+      _ => None,
+    }
+  }
+}
+
+impl Codec for char {
+  fn encode(&self, out: &mut Vec<u8>) {
+    (*self as u32).encode(out);
+  }
+
+  fn decode(input: &mut &[u8]) -> Option<Self> {
+    char::from_u32(u32::decode(input)?)
+  }
+}
+
+impl Codec for String {
+  fn encode(&self, out: &mut Vec<u8>) {
+    write_varint(out, self.len() as u64);
+    out.extend_from_slice(self.as_bytes());
+  }
+
+  fn decode(input: &mut &[u8]) -> Option<Self> {
+    String::from_utf8(read_bytes(input)?.to_vec()).ok()
+  }
+}
+
+impl Codec for Vec<u8> {
+  fn encode(&self, out: &mut Vec<u8>) {
+    write_varint(out, self.len() as u64);
+    out.extend_from_slice(self);
+  }
+
+  fn decode(input: &mut &[u8]) -> Option<Self> {
+    read_bytes(input).map(<[u8]>::to_vec)
+  }
+}
+
+impl<A: Codec, B: Codec> Codec for (A, B) {
+  fn encode(&self, out: &mut Vec<u8>) {
+    self.0.encode(out);
+    self.1.encode(out);
+  }
+
+  fn decode(input: &mut &[u8]) -> Option<Self> {
+    Some((A::decode(input)?, B::decode(input)?))
+  }
+}
+
+// CRC-32 (IEEE 802.3)，按位计算，不需要查找表
+pub(crate) fn crc32(bytes: &[u8]) -> u32 {
+  let mut crc = !0u32;
+  for &byte in bytes {
+    crc ^= u32::from(byte);
+    for _ in 0..8 {
+      crc = (crc >> 1) ^ (0xedb8_8320 & (crc & 1).wrapping_neg());
+    }
+  }
+  !crc
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{crc32, read_varint, write_varint, Codec};
+
+  fn round_trip<T: Codec + PartialEq + std::fmt::Debug>(value: T) {
+    let mut out = Vec::new();
+    value.encode(&mut out);
+    let mut input = &out[..];
+    assert_eq!(T::decode(&mut input), Some(value));
+    assert!(input.is_empty());
+  }
+
+  #[test]
+  fn test_codec() {
+    assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+    for value in [0, 1, 127, 128, 300, u64::MAX] {
+      round_trip(value);
+    }
+    for value in [0, -1, 1, i64::MIN, i64::MAX] {
+      round_trip(value);
+    }
+    round_trip(("key".to_string(), vec![0u8, 255]));
+    round_trip(('中', true));
+
+    let mut out = Vec::new();
+    write_varint(&mut out, 300);
+    assert_eq!(out, [0xac, 0x02]);
+    // 不完整的变长整数与超出目标类型范围的值
+    assert_eq!(read_varint(&mut &[0x80][..]), None);
+    assert_eq!(u8::decode(&mut &out[..]), None);
+    assert_eq!(bool::decode(&mut &[2][..]), None);
+  }
+}
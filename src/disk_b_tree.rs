@@ -0,0 +1,940 @@
+use std::{
+  cmp::Ordering,
+  collections::HashMap,
+  error::Error,
+  fmt::{self, Display},
+  fs::{File, OpenOptions},
+  io::{self, Read, Seek, SeekFrom, Write},
+  marker::PhantomData,
+  mem,
+  path::{Path, PathBuf},
+  vec,
+};
+
+use crate::codec::{crc32, Codec};
+
+// 文件由固定大小的页组成，第 0 页为文件头(小端)：
+//   magic "BTRD" | version: u16 | page_size: u32 | t: u32 | root: u32 | 页数: u32 | 键数: u64
+// 其余每页存放一个节点：
+//   是否为叶子: u8 | 键数: u16 | 孩子页号: u32 * (键数 + 1)，仅内部节点 | 键...
+// 键使用 Codec 编码，整数为变长编码，字符串与字节串带长度前缀。
+// 对数据文件的修改先以整页的形式写入同目录下的 "<文件名>-wal" 日志，见 BufferPool::commit
+pub const PAGE_SIZE: usize = 4096;
+const MAGIC: &[u8; 4] = b"BTRD";
+const VERSION: u16 = 1;
+const HEADER_PAGE: PageId = 0;
+const NODE_HEADER_LEN: usize = 3;
+
+pub type PageId = u32;
+
+#[derive(Debug)]
+pub enum DiskError {
+  Io(io::Error),
+  // 文件开头不是磁盘 B 树的 magic
+  BadMagic,
+  UnsupportedVersion { version: u16 },
+  PageSizeMismatch { page_size: u32 },
+  // 页的内容无法解码，或者文件长度与文件头不一致
+  Corrupted { page: PageId },
+  // 编码后的键超过单页能够容纳的大小
+  KeyTooLarge { len: usize, max: usize },
+  // 之前的写入失败，内存中的状态已不可信，需要重新打开
+  Poisoned,
+  // 最小度数小于 2，或者大到一个节点放不进一页
+  InvalidDegree { t: usize },
+  // 缓冲池至少需要一页
+  EmptyBufferPool,
+}
+
+impl Display for DiskError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      DiskError::Io(e) => write!(f, "io error: {}", e),
+      DiskError::BadMagic => write!(f, "not a disk B-tree file"),
+      DiskError::UnsupportedVersion { version } => {
+        write!(f, "unsupported file version {}", version)
+      }
+      DiskError::PageSizeMismatch { page_size } => write!(
+        f,
+        "file uses {} byte pages, expected {}",
+        page_size, PAGE_SIZE
+      ),
+      DiskError::Corrupted { page } => write!(f, "page {} is corrupted", page),
+      DiskError::KeyTooLarge { len, max } => write!(
+        f,
+        "encoded key is {} bytes, at most {} fit in a page",
+        len, max
+      ),
+      DiskError::Poisoned => write!(f, "tree is unusable after a failed write, reopen it"),
+      DiskError::InvalidDegree { t } => write!(
+        f,
+        "minimum degree {} must be at least 2 and fit in a {} byte page",
+        t, PAGE_SIZE
+      ),
+      DiskError::EmptyBufferPool => write!(f, "buffer pool needs at least one page"),
+    }
+  }
+}
+
+impl Error for DiskError {
+  fn source(&self) -> Option<&(dyn Error + 'static)> {
+    match self {
+      DiskError::Io(e) => Some(e),
+      _ => None,
+    }
+  }
+}
+
+impl From<io::Error> for DiskError {
+  fn from(e: io::Error) -> Self {
+    DiskError::Io(e)
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FrameState {
+  Clean,
+  // 已经提交并写入日志，尚未写回数据文件
+  Logged,
+  // 当前操作修改过、尚未提交的页，不能被淘汰
+  Uncommitted,
+}
+
+#[derive(Debug)]
+struct Frame {
+  data: Vec<u8>,
+  state: FrameState,
+  last_used: u64,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Target {
+  Data,
+  Log,
+}
+
+// 缓存最近使用的页，写入只修改缓存中的页。每个操作结束时把修改过的页作为一条记录追加到
+// 预写日志并同步，之后这些页才可以被淘汰写回数据文件；flush 写回所有页后清空日志
+#[derive(Debug)]
+struct BufferPool {
+  file: File,
+  wal: File,
+  capacity: usize,
+  frames: HashMap<PageId, Frame>,
+  clock: u64,
+  page_count: u32,
+  // 写文件出错后内存中的页与磁盘不再一致，拒绝之后的所有操作
+  poisoned: bool,
+  // 测试用的故障注入：还允许的写文件次数，用完时只写入一半数据后返回错误
+  crash_after: Option<usize>,
+}
+
+impl BufferPool {
+  fn new(file: File, wal: File, capacity: usize, page_count: u32) -> Self {
+    debug_assert!(capacity >= 1);
+    Self {
+      file,
+      wal,
+      capacity,
+      frames: HashMap::with_capacity(capacity),
+      clock: 0,
+      page_count,
+      poisoned: false,
+      crash_after: None,
+    }
+  }
+
+  fn tick(&mut self) -> u64 {
+    self.clock += 1;
+    self.clock
+  }
+
+  fn check(&self) -> Result<(), DiskError> {
+    if self.poisoned {
+      Err(DiskError::Poisoned)
+    } else {
+      Ok(())
+    }
+  }
+
+  // 所有写文件的操作都经过这里，出错时将缓冲池标记为不可用
+  fn write_point<R>(
+    &mut self,
+    target: Target,
+    op: impl FnOnce(&mut File, Option<usize>) -> io::Result<R>,
+  ) -> Result<R, DiskError> {
+    self.check()?;
+    let partial = match &mut self.crash_after {
+      Some(0) => Some(0),
+      Some(left) => {
+        *left -= 1;
+        None
+      }
+      None => None,
+    };
+    let file = match target {
+      Target::Data => &mut self.file,
+      Target::Log => &mut self.wal,
+    };
+    let result = op(file, partial).and_then(|r| match partial {
+      Some(_) => Err(io::Error::other("simulated crash")),
+      None => Ok(r),
+    });
+    result.map_err(|e| {
+      self.poisoned = true;
+      DiskError::Io(e)
+    })
+  }
+
+  fn write_at(&mut self, target: Target, offset: u64, data: &[u8]) -> Result<(), DiskError> {
+    self.write_point(target, |file, partial| {
+      file.seek(SeekFrom::Start(offset))?;
+      // 模拟崩溃时只写入一半，产生一次不完整的写
+      let len = partial.map_or(data.len(), |_| data.len() / 2);
+      file.write_all(&data[..len])
+    })
+  }
+
+  fn sync(&mut self, target: Target) -> Result<(), DiskError> {
+    self.write_point(target, |file, partial| match partial {
+      Some(_) => Ok(()),
+      None => file.sync_data(),
+    })
+  }
+
+  fn truncate_log(&mut self) -> Result<(), DiskError> {
+    self.write_point(Target::Log, |file, partial| match partial {
+      Some(_) => Ok(()),
+      None => file.set_len(0),
+    })
+  }
+
+  fn page_offset(id: PageId) -> u64 {
+    id as u64 * PAGE_SIZE as u64
+  }
+
+  // 为新页腾出位置，淘汰最久未使用且已提交的页，都未提交时暂时超出容量
+  fn make_room(&mut self) -> Result<(), DiskError> {
+    while self.frames.len() >= self.capacity {
+      let Some((&id, _)) = self
+        .frames
+        .iter()
+        .filter(|(_, frame)| frame.state != FrameState::Uncommitted)
+        .min_by_key(|(_, frame)| frame.last_used)
+      else {
+        break;
+      };
+      let frame = self.frames.remove(&id).unwrap();
+      if frame.state == FrameState::Logged {
+        self.write_at(Target::Data, Self::page_offset(id), &frame.data)?;
+      }
+    }
+    Ok(())
+  }
+
+  fn read(&mut self, id: PageId) -> Result<&[u8], DiskError> {
+    self.check()?;
+    let now = self.tick();
+    if !self.frames.contains_key(&id) {
+      self.make_room()?;
+      let mut data = vec![0; PAGE_SIZE];
+      self.file.seek(SeekFrom::Start(Self::page_offset(id)))?;
+      self.file.read_exact(&mut data)?;
+      self.frames.insert(
+        id,
+        Frame {
+          data,
+          state: FrameState::Clean,
+          last_used: now,
+        },
+      );
+    }
+    let frame = self.frames.get_mut(&id).unwrap();
+    frame.last_used = now;
+    Ok(&frame.data)
+  }
+
+  // 覆盖整页内容，不足一页的部分补 0
+  fn write(&mut self, id: PageId, mut data: Vec<u8>) -> Result<(), DiskError> {
+    self.check()?;
+    debug_assert!(data.len() <= PAGE_SIZE);
+    data.resize(PAGE_SIZE, 0);
+    let last_used = self.tick();
+    if !self.frames.contains_key(&id) {
+      self.make_room()?;
+    }
+    self.frames.insert(
+      id,
+      Frame {
+        data,
+        state: FrameState::Uncommitted,
+        last_used,
+      },
+    );
+    Ok(())
+  }
+
+  fn has_uncommitted(&self) -> bool {
+    self
+      .frames
+      .values()
+      .any(|frame| frame.state == FrameState::Uncommitted)
+  }
+
+  fn allocate(&mut self) -> PageId {
+    let id = self.page_count;
+    self.page_count += 1;
+    id
+  }
+
+  // 日志记录：页数: u32 | (页号: u32 | 页内容)... | crc32: u32，校验和覆盖之前的所有字节
+  fn commit(&mut self) -> Result<(), DiskError> {
+    let mut ids: Vec<_> = self
+      .frames
+      .iter()
+      .filter(|(_, frame)| frame.state == FrameState::Uncommitted)
+      .map(|(&id, _)| id)
+      .collect();
+    if ids.is_empty() {
+      return Ok(());
+    }
+    ids.sort_unstable();
+    let mut record = Vec::with_capacity(8 + ids.len() * (4 + PAGE_SIZE));
+    record.extend_from_slice(&(ids.len() as u32).to_le_bytes());
+    for &id in &ids {
+      record.extend_from_slice(&id.to_le_bytes());
+      record.extend_from_slice(&self.frames[&id].data);
+    }
+    let checksum = crc32(&record);
+    record.extend_from_slice(&checksum.to_le_bytes());
+    let end = self.wal.seek(SeekFrom::End(0))?;
+    self.write_at(Target::Log, end, &record)?;
+    self.sync(Target::Log)?;
+    for id in ids {
+      self.frames.get_mut(&id).unwrap().state = FrameState::Logged;
+    }
+    Ok(())
+  }
+
+  // 检查点：把已提交的页写回数据文件并同步，之后日志中的记录不再需要
+  fn checkpoint(&mut self) -> Result<(), DiskError> {
+    self.check()?;
+    let mut ids: Vec<_> = self
+      .frames
+      .iter()
+      .filter(|(_, frame)| frame.state == FrameState::Logged)
+      .map(|(&id, _)| id)
+      .collect();
+    // 按页号顺序写回，尽量顺序写文件
+    ids.sort_unstable();
+    for &id in &ids {
+      let data = mem::take(&mut self.frames.get_mut(&id).unwrap().data);
+      let result = self.write_at(Target::Data, Self::page_offset(id), &data);
+      let frame = self.frames.get_mut(&id).unwrap();
+      frame.data = data;
+      result?;
+      frame.state = FrameState::Clean;
+    }
+    self.sync(Target::Data)?;
+    self.truncate_log()?;
+    self.sync(Target::Log)
+  }
+
+  // 打开时重放日志中完整的记录，末尾不完整或校验失败的记录属于未完成的操作，直接丢弃
+  fn recover(&mut self) -> Result<(), DiskError> {
+    let mut log = Vec::new();
+    self.wal.seek(SeekFrom::Start(0))?;
+    self.wal.read_to_end(&mut log)?;
+    if log.is_empty() {
+      return Ok(());
+    }
+    let mut input = &log[..];
+    while let Some((count, rest)) = input.split_first_chunk::<4>() {
+      let count = u32::from_le_bytes(*count) as usize;
+      let len = 4 + count.saturating_mul(4 + PAGE_SIZE);
+      if count == 0 || input.len() < len + 4 {
+        break;
+      }
+      let (record, checksum) = input[..len + 4].split_at(len);
+      if crc32(record) != u32::from_le_bytes(checksum.try_into().unwrap()) {
+        break;
+      }
+      for entry in rest[..len - 4].chunks_exact(4 + PAGE_SIZE) {
+        let (id, data) = entry.split_at(4);
+        let id = PageId::from_le_bytes(id.try_into().unwrap());
+        self.write_at(Target::Data, Self::page_offset(id), data)?;
+      }
+      input = &input[len + 4..];
+    }
+    self.sync(Target::Data)?;
+    self.truncate_log()?;
+    self.sync(Target::Log)
+  }
+}
+
+#[derive(Debug)]
+struct DiskNode<T> {
+  keys: Vec<T>,
+  children: Vec<PageId>,
+}
+
+impl<T: Codec> DiskNode<T> {
+  fn is_leaf(&self) -> bool {
+    self.children.is_empty()
+  }
+
+  fn encode(&self) -> Vec<u8> {
+    let mut out = Vec::with_capacity(PAGE_SIZE);
+    out.push(self.is_leaf() as u8);
+    out.extend_from_slice(&(self.keys.len() as u16).to_le_bytes());
+    for child in &self.children {
+      out.extend_from_slice(&child.to_le_bytes());
+    }
+    for key in &self.keys {
+      key.encode(&mut out);
+    }
+    out
+  }
+
+  fn decode(mut page: &[u8]) -> Option<Self> {
+    let leaf = match page.first()? {
+      0 => false,
+      1 => true,
+      _ => return None,
+    };
+    let len = u16::from_le_bytes(page.get(1..3)?.try_into().unwrap()) as usize;
+    page = &page[NODE_HEADER_LEN..];
+    let mut children = Vec::new();
+    if !leaf {
+      for _ in 0..=len {
+        let (child, rest) = page.split_first_chunk::<4>()?;
+        children.push(PageId::from_le_bytes(*child));
+        page = rest;
+      }
+    }
+    let keys = (0..len)
+      .map(|_| T::decode(&mut page))
+      .collect::<Option<_>>()?;
+    Some(Self { keys, children })
+  }
+}
+
+// 节点以页为单位保存在文件中的 B 树集合，t 为最小度数，与 BTree::new(t) 相同。
+// 每次插入返回前修改都已写入预写日志，进程崩溃后 open 会重放日志恢复到最后一次成功的操作；
+// flush 或 drop 时把页写回数据文件并清空日志
+#[derive(Debug)]
+pub struct DiskBTree<T> {
+  pool: BufferPool,
+  t: usize,
+  root: PageId,
+  length: u64,
+  marker: PhantomData<T>,
+}
+
+// 单个键编码后的最大字节数，保证 2t-1 个键与 2t 个孩子页号能放进一页
+fn max_key_len(t: usize) -> usize {
+  (PAGE_SIZE - NODE_HEADER_LEN - 4 * 2 * t) / (2 * t - 1)
+}
+
+// 数据文件对应的预写日志路径，在文件名后加上 "-wal"
+pub fn wal_path<P: AsRef<Path>>(path: P) -> PathBuf {
+  let mut wal = path.as_ref().as_os_str().to_owned();
+  wal.push("-wal");
+  PathBuf::from(wal)
+}
+
+fn open_file(path: &Path, create: bool) -> io::Result<File> {
+  OpenOptions::new()
+    .read(true)
+    .write(true)
+    .create(create)
+    .truncate(create)
+    .open(path)
+}
+
+impl<T: Codec + Ord> DiskBTree<T> {
+  // 创建新文件，已存在的文件会被清空，缓冲池最多缓存 pool_pages 页
+  pub fn create<P: AsRef<Path>>(path: P, t: usize, pool_pages: usize) -> Result<Self, DiskError> {
+    // 在清空文件之前检查参数
+    if t < 2 || 2 * t * 4 >= PAGE_SIZE || max_key_len(t) < 8 {
+      return Err(DiskError::InvalidDegree { t });
+    }
+    if pool_pages == 0 {
+      return Err(DiskError::EmptyBufferPool);
+    }
+    let file = open_file(path.as_ref(), true)?;
+    let wal = open_file(&wal_path(&path), true)?;
+    let mut tree = Self {
+      pool: BufferPool::new(file, wal, pool_pages, 0),
+      t,
+      root: 0,
+      length: 0,
+      marker: PhantomData,
+    };
+    let header = tree.pool.allocate();
+    debug_assert_eq!(header, HEADER_PAGE);
+    tree.root = tree.pool.allocate();
+    tree.store(
+      tree.root,
+      &DiskNode {
+        keys: Vec::new(),
+        children: Vec::new(),
+      },
+    )?;
+    tree.commit()?;
+    tree.flush()?;
+    Ok(tree)
+  }
+
+  pub fn open<P: AsRef<Path>>(path: P, pool_pages: usize) -> Result<Self, DiskError> {
+    if pool_pages == 0 {
+      return Err(DiskError::EmptyBufferPool);
+    }
+    let file = open_file(path.as_ref(), false)?;
+    let wal = OpenOptions::new()
+      .read(true)
+      .write(true)
+      .create(true)
+      .truncate(false)
+      .open(wal_path(&path))?;
+    let mut pool = BufferPool::new(file, wal, pool_pages, 1);
+    pool.recover()?;
+    let file_len = pool.file.metadata()?.len();
+    if file_len < PAGE_SIZE as u64 {
+      return Err(DiskError::BadMagic);
+    }
+    let page = pool.read(HEADER_PAGE)?;
+    if &page[..4] != MAGIC {
+      return Err(DiskError::BadMagic);
+    }
+    let u32_at = |offset: usize| u32::from_le_bytes(page[offset..offset + 4].try_into().unwrap());
+    let version = u16::from_le_bytes([page[4], page[5]]);
+    if version != VERSION {
+      return Err(DiskError::UnsupportedVersion { version });
+    }
+    let page_size = u32_at(6);
+    if page_size as usize != PAGE_SIZE {
+      return Err(DiskError::PageSizeMismatch { page_size });
+    }
+    let (t, root, page_count) = (u32_at(10) as usize, u32_at(14), u32_at(18));
+    let length = u64::from_le_bytes(page[22..30].try_into().unwrap());
+    let corrupted = DiskError::Corrupted { page: HEADER_PAGE };
+    if t < 2 || max_key_len(t) < 8 || root == HEADER_PAGE || root >= page_count {
+      return Err(corrupted);
+    }
+    if (page_count as u64) * (PAGE_SIZE as u64) > file_len {
+      return Err(corrupted);
+    }
+    pool.page_count = page_count;
+    Ok(Self {
+      pool,
+      t,
+      root,
+      length,
+      marker: PhantomData,
+    })
+  }
+
+  fn load(&mut self, id: PageId) -> Result<DiskNode<T>, DiskError> {
+    let page_count = self.pool.page_count;
+    let page = self.pool.read(id)?;
+    DiskNode::decode(page)
+      .filter(|node| {
+        node
+          .children
+          .iter()
+          .all(|&child| child != HEADER_PAGE && child < page_count)
+      })
+      .ok_or(DiskError::Corrupted { page: id })
+  }
+
+  fn store(&mut self, id: PageId, node: &DiskNode<T>) -> Result<(), DiskError> {
+    self.pool.write(id, node.encode())
+  }
+
+  pub fn contains(&mut self, key: &T) -> Result<bool, DiskError> {
+    let mut id = self.root;
+    loop {
+      let node = self.load(id)?;
+      match node.keys.binary_search(key) {
+        Ok(_) => return Ok(true),
+        Err(_) if node.is_leaf() => return Ok(false),
+        Err(index) => id = node.children[index],
+      }
+    }
+  }
+
+  // 插入键，已存在时返回 false
+  pub fn insert(&mut self, key: T) -> Result<bool, DiskError> {
+    let mut encoded = Vec::new();
+    key.encode(&mut encoded);
+    let max = max_key_len(self.t);
+    if encoded.len() > max {
+      return Err(DiskError::KeyTooLarge {
+        len: encoded.len(),
+        max,
+      });
+    }
+    // 修改到一半出错时缓冲池中留有未提交的页，之后的操作都会失败，重新打开后回到上次提交的状态
+    let result = self.insert_new(key).and_then(|inserted| {
+      self.commit()?;
+      Ok(inserted)
+    });
+    if result.is_err() {
+      self.pool.poisoned = true;
+    }
+    result
+  }
+
+  // 键已存在时返回 false，此前下降过程中做过的分裂仍然保留并提交
+  fn insert_new(&mut self, key: T) -> Result<bool, DiskError> {
+    // 根节点已满时先分裂，树长高一层
+    if self.load(self.root)?.keys.len() == 2 * self.t - 1 {
+      let mut root = DiskNode {
+        keys: Vec::new(),
+        children: vec![self.root],
+      };
+      let id = self.pool.allocate();
+      self.split_child(id, &mut root, 0)?;
+      self.root = id;
+    }
+    let inserted = self.insert_non_full(key)?;
+    if inserted {
+      self.length += 1;
+    }
+    Ok(inserted)
+  }
+
+  // 分裂 parent 已满的第 index 个孩子，中间的键上移到 parent
+  fn split_child(
+    &mut self,
+    parent_id: PageId,
+    parent: &mut DiskNode<T>,
+    index: usize,
+  ) -> Result<(), DiskError> {
+    let child_id = parent.children[index];
+    let mut child = self.load(child_id)?;
+    let right = DiskNode {
+      keys: child.keys.split_off(self.t),
+      children: if child.is_leaf() {
+        Vec::new()
+      } else {
+        child.children.split_off(self.t)
+      },
+    };
+    let median = child.keys.pop().unwrap();
+    let right_id = self.pool.allocate();
+    self.store(child_id, &child)?;
+    self.store(right_id, &right)?;
+    parent.keys.insert(index, median);
+    parent.children.insert(index + 1, right_id);
+    self.store(parent_id, parent)
+  }
+
+  // 自顶向下插入，下降前分裂已满的孩子，保证插入的叶子不会溢出；
+  // 在同一次下降中检查键是否已存在，存在时返回 false
+  fn insert_non_full(&mut self, key: T) -> Result<bool, DiskError> {
+    let mut id = self.root;
+    loop {
+      let mut node = self.load(id)?;
+      let Err(mut index) = node.keys.binary_search(&key) else {
+        return Ok(false);
+      };
+      if node.is_leaf() {
+        node.keys.insert(index, key);
+        self.store(id, &node)?;
+        return Ok(true);
+      }
+      if self.load(node.children[index])?.keys.len() == 2 * self.t - 1 {
+        self.split_child(id, &mut node, index)?;
+        // 上移的中间键可能正好等于 key
+        match node.keys[index].cmp(&key) {
+          Ordering::Less => index += 1,
+          Ordering::Equal => return Ok(false),
+          Ordering::Greater => {}
+        }
+      }
+      id = node.children[index];
+    }
+  }
+
+  // 按顺序遍历所有键，每次只把路径上的节点读入内存
+  pub fn iter(&mut self) -> Iter<'_, T> {
+    Iter {
+      descend: Some(self.root),
+      tree: self,
+      stack: Vec::new(),
+    }
+  }
+}
+
+impl<T> DiskBTree<T> {
+  // 更新文件头，并将本次操作修改的页作为一条记录写入日志
+  fn commit(&mut self) -> Result<(), DiskError> {
+    // 插入已存在的键且没有分裂任何节点时不需要写日志
+    if !self.pool.has_uncommitted() {
+      return Ok(());
+    }
+    let mut header = Vec::with_capacity(30);
+    header.extend_from_slice(MAGIC);
+    header.extend_from_slice(&VERSION.to_le_bytes());
+    header.extend_from_slice(&(PAGE_SIZE as u32).to_le_bytes());
+    header.extend_from_slice(&(self.t as u32).to_le_bytes());
+    header.extend_from_slice(&self.root.to_le_bytes());
+    header.extend_from_slice(&self.pool.page_count.to_le_bytes());
+    header.extend_from_slice(&self.length.to_le_bytes());
+    self.pool.write(HEADER_PAGE, header)?;
+    self.pool.commit()
+  }
+
+  // 把已提交的页写回数据文件并清空日志
+  pub fn flush(&mut self) -> Result<(), DiskError> {
+    self.pool.checkpoint()
+  }
+
+  pub fn len(&self) -> usize {
+    self.length as usize
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.length == 0
+  }
+}
+
+impl<T> Drop for DiskBTree<T> {
+  fn drop(&mut self) {
+    // drop 中无法返回错误，需要确认写入成功时应先调用 flush；
+    // 即使写回失败，已提交的修改仍在日志中，下次打开时恢复
+    if !self.pool.poisoned {
+      let _ = self.flush();
+    }
+  }
+}
+
+pub struct Iter<'a, T> {
+  tree: &'a mut DiskBTree<T>,
+  // 每层尚未输出的键与尚未访问的孩子
+  stack: Vec<(vec::IntoIter<T>, vec::IntoIter<PageId>)>,
+  // 下一步需要下降到的子树
+  descend: Option<PageId>,
+}
+
+impl<'a, T: Codec + Ord> Iterator for Iter<'a, T> {
+  type Item = Result<T, DiskError>;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    loop {
+      if let Some(id) = self.descend.take() {
+        let node = match self.tree.load(id) {
+          Ok(node) => node,
+          Err(e) => {
+            self.stack.clear();
+            return Some(Err(e));
+          }
+        };
+        let mut children = node.children.into_iter();
+        self.descend = children.next();
+        self.stack.push((node.keys.into_iter(), children));
+        continue;
+      }
+      let (keys, children) = self.stack.last_mut()?;
+      match keys.next() {
+        Some(key) => {
+          self.descend = children.next();
+          return Some(Ok(key));
+        }
+        None => {
+          self.stack.pop();
+        }
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{wal_path, DiskBTree, DiskError, PAGE_SIZE};
+  use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    process,
+  };
+
+  // 临时数据文件，drop 时连同日志一起删除，断言失败时也不会遗留文件
+  struct TempFile(PathBuf);
+
+  impl TempFile {
+    fn new(name: &str) -> Self {
+      Self(env::temp_dir().join(format!("disk_b_tree_{}_{}.db", name, process::id())))
+    }
+  }
+
+  impl AsRef<Path> for TempFile {
+    fn as_ref(&self) -> &Path {
+      &self.0
+    }
+  }
+
+  impl Drop for TempFile {
+    fn drop(&mut self) {
+      let _ = fs::remove_file(&self.0);
+      let _ = fs::remove_file(wal_path(&self.0));
+    }
+  }
+
+  #[test]
+  fn test_disk_b_tree() {
+    let path = TempFile::new("ints");
+    let keys: Vec<i64> = (0..3000).map(|i| (i * 7919) % 3001 - 1500).collect();
+    {
+      // 缓冲池只有 4 页，插入过程中不断淘汰并写回脏页
+      let mut tree = DiskBTree::create(&path, 8, 4).unwrap();
+      for &key in &keys {
+        assert!(tree.insert(key).unwrap());
+      }
+      // 重复的键在同一次下降中被发现，不改变 len
+      for &key in keys.iter().step_by(7) {
+        assert!(!tree.insert(key).unwrap());
+      }
+      assert!(tree.pool.frames.len() <= 4);
+      assert_eq!(tree.len(), keys.len());
+      assert!(tree.contains(&-1500).unwrap());
+      assert!(!tree.contains(&1501).unwrap());
+      tree.flush().unwrap();
+    }
+    assert_eq!(fs::metadata(&path).unwrap().len() % PAGE_SIZE as u64, 0);
+
+    // 重新打开后内容不变，并且可以继续插入
+    let mut expected = keys.clone();
+    expected.sort_unstable();
+    {
+      let mut tree = DiskBTree::<i64>::open(&path, 16).unwrap();
+      assert_eq!(tree.len(), keys.len());
+      let all: Vec<_> = tree.iter().collect::<Result<_, _>>().unwrap();
+      assert_eq!(all, expected);
+      assert!(tree.insert(5000).unwrap());
+      // 不调用 flush，由 drop 写回
+    }
+    let mut tree = DiskBTree::<i64>::open(&path, 16).unwrap();
+    assert!(tree.contains(&5000).unwrap());
+    assert_eq!(tree.len(), keys.len() + 1);
+    drop(tree);
+
+    // 文件头损坏或不是本格式的文件
+    let mut bytes = fs::read(&path).unwrap();
+    bytes[0] = b'X';
+    fs::write(&path, &bytes).unwrap();
+    assert!(matches!(
+      DiskBTree::<i64>::open(&path, 4),
+      Err(DiskError::BadMagic)
+    ));
+    // 参数不合法时返回错误，并且不会创建或清空文件
+    let path = TempFile::new("params");
+    for t in [0, 1, PAGE_SIZE] {
+      assert!(matches!(
+        DiskBTree::<i64>::create(&path, t, 4),
+        Err(DiskError::InvalidDegree { t: bad }) if bad == t
+      ));
+    }
+    assert!(matches!(
+      DiskBTree::<i64>::create(&path, 8, 0),
+      Err(DiskError::EmptyBufferPool)
+    ));
+    assert!(!path.0.exists());
+    assert!(matches!(
+      DiskBTree::<i64>::open(&path, 0),
+      Err(DiskError::EmptyBufferPool)
+    ));
+  }
+
+  #[test]
+  fn test_disk_b_tree_strings() {
+    let path = TempFile::new("strings");
+    {
+      let mut tree = DiskBTree::create(&path, 4, 2).unwrap();
+      for i in 0..500 {
+        tree.insert(format!("key-{:04}", (i * 13) % 500)).unwrap();
+      }
+      let max = (PAGE_SIZE - 3 - 4 * 8) / 7;
+      assert!(matches!(
+        tree.insert("x".repeat(max)),
+        Err(DiskError::KeyTooLarge { max: m, .. }) if m == max
+      ));
+      assert!(tree.insert("x".repeat(max - 2)).unwrap());
+    }
+    let mut tree = DiskBTree::<String>::open(&path, 2).unwrap();
+    let all: Vec<_> = tree.iter().collect::<Result<_, _>>().unwrap();
+    assert_eq!(all.len(), 501);
+    assert_eq!(all[0], "key-0000");
+    assert!(all.windows(2).all(|w| w[0] < w[1]));
+  }
+
+  // 在第 n 次写文件时模拟崩溃(该次写入只完成一半)，对每个 n 检查重新打开后的内容：
+  // 已经返回成功的插入都在，崩溃时正在进行的插入要么完整存在要么完全不存在
+  #[test]
+  fn test_disk_b_tree_crash_recovery() {
+    let base_path = TempFile::new("crash_base");
+    let base: Vec<i32> = (0..60).map(|i| i * 10).collect();
+    {
+      let mut tree = DiskBTree::create(&base_path, 2, 3).unwrap();
+      for &key in &base {
+        tree.insert(key).unwrap();
+      }
+      tree.flush().unwrap();
+    }
+    // 插入到已有的叶子之间，触发多层分裂，缓冲池很小以便在操作之间淘汰写回
+    let workload: Vec<i32> = (0..24).map(|i| (i * 37) % 600 + 5).collect();
+    let expected = |done: usize| {
+      let mut keys: Vec<_> = base.iter().chain(&workload[..done]).copied().collect();
+      keys.sort_unstable();
+      keys.dedup();
+      keys
+    };
+
+    let path = TempFile::new("crash");
+    for crash_at in 0.. {
+      fs::copy(&base_path, &path).unwrap();
+      let _ = fs::remove_file(wal_path(&path));
+      let mut tree = DiskBTree::<i32>::open(&path, 3).unwrap();
+      tree.pool.crash_after = Some(crash_at);
+      let mut done = 0;
+      let mut crashed = false;
+      for &key in &workload {
+        if tree.insert(key).is_err() {
+          crashed = true;
+          break;
+        }
+        done += 1;
+      }
+      if !crashed {
+        crashed = tree.flush().is_err();
+      }
+      if crashed {
+        assert!(matches!(tree.insert(-1), Err(DiskError::Poisoned)));
+      }
+      drop(tree);
+
+      let mut tree = DiskBTree::<i32>::open(&path, 3).unwrap();
+      let keys: Vec<_> = tree.iter().collect::<Result<_, _>>().unwrap();
+      assert!(
+        keys == expected(done) || keys == expected(done + 1),
+        "crash at write {} after {} inserts",
+        crash_at,
+        done
+      );
+      assert_eq!(tree.len(), keys.len());
+      // 恢复后的树可以继续正常使用
+      assert!(tree.insert(1000).unwrap());
+      drop(tree);
+      let mut tree = DiskBTree::<i32>::open(&path, 3).unwrap();
+      assert!(tree.contains(&1000).unwrap());
+      assert_eq!(tree.len(), keys.len() + 1);
+      drop(tree);
+
+      if !crashed {
+        assert!(crash_at > workload.len());
+        break;
+      }
+    }
+  }
+}
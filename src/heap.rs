@@ -33,7 +33,7 @@ impl<T: Ord> Heap<T> {
     Some(val)
   }
   pub fn peek(&self) -> Option<&T> {
-    self.data.get(0)
+    self.data.first()
   }
   pub fn is_empty(&self) -> bool {
     self.data.is_empty()
@@ -0,0 +1,240 @@
+use std::{
+  error::Error,
+  fmt::{self, Display},
+  ops::{Bound, RangeBounds, RangeInclusive},
+};
+
+use crate::avl_tree::{self, AVLNode, Natural, Summary};
+
+// 子树中所有区间右端点的最大值，空子树为 None
+#[derive(Debug, Clone)]
+pub(crate) struct MaxEnd<T>(Option<T>);
+
+impl<T: Ord + Clone, V> Summary<(T, T), V> for MaxEnd<T> {
+  fn empty() -> Self {
+    MaxEnd(None)
+  }
+
+  fn single(key: &(T, T), _value: &V) -> Self {
+    MaxEnd(Some(key.1.clone()))
+  }
+
+  fn combine(left: &Self, right: &Self) -> Self {
+    MaxEnd(match (&left.0, &right.0) {
+      (Some(a), Some(b)) => Some(a.max(b).clone()),
+      (a, b) => a.as_ref().or(b.as_ref()).cloned(),
+    })
+  }
+}
+
+type Node<T, V> = AVLNode<(T, T), V, MaxEnd<T>>;
+
+// insert_interval 收到起点大于终点的区间时返回的错误
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InvalidInterval;
+
+impl Display for InvalidInterval {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "interval start is greater than its end")
+  }
+}
+
+impl Error for InvalidInterval {}
+
+// 区间树，节点按 (起点, 终点) 排序，并在子树摘要中维护最大右端点，区间均为闭区间
+#[derive(Debug)]
+pub struct IntervalTree<T, V> {
+  root: Option<Box<Node<T, V>>>,
+  length: usize,
+}
+
+impl<T: Ord + Clone, V> Default for IntervalTree<T, V> {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl<T: Ord + Clone, V> IntervalTree<T, V> {
+  pub fn new() -> Self {
+    Self {
+      root: None,
+      length: 0,
+    }
+  }
+
+  // 插入区间，区间已存在时替换值并返回旧值；起点大于终点时不修改树并返回 InvalidInterval
+  pub fn insert_interval(
+    &mut self,
+    interval: RangeInclusive<T>,
+    value: V,
+  ) -> Result<Option<V>, InvalidInterval> {
+    let (start, end) = interval.into_inner();
+    if start > end {
+      return Err(InvalidInterval);
+    }
+    let old = avl_tree::insert(&mut self.root, (start, end), value, &Natural).err();
+    if old.is_none() {
+      self.length += 1;
+    }
+    Ok(old)
+  }
+
+  // 删除与给定端点完全相同的区间，返回其值
+  pub fn remove_interval(&mut self, interval: &RangeInclusive<T>) -> Option<V> {
+    let key = (interval.start().clone(), interval.end().clone());
+    let removed = avl_tree::remove(&mut self.root, &key, &Natural);
+    if removed.is_some() {
+      self.length -= 1;
+    }
+    removed.map(|(_, value)| value)
+  }
+
+  // 按起点顺序返回所有与 range 相交的区间
+  pub fn overlapping<R: RangeBounds<T>>(&self, range: R) -> Overlapping<'_, T, V> {
+    let mut iter = Overlapping {
+      node_stack: Vec::with_capacity(self.root.as_ref().map_or(0, |n| n.height)),
+      start: range.start_bound().cloned(),
+      end: range.end_bound().cloned(),
+    };
+    iter.push_left_path(&self.root);
+    iter
+  }
+
+  // 返回所有包含 point 的区间
+  pub fn stabbing(&self, point: &T) -> Overlapping<'_, T, V> {
+    self.overlapping(point.clone()..=point.clone())
+  }
+
+  pub fn len(&self) -> usize {
+    self.length
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.length == 0
+  }
+}
+
+pub struct Overlapping<'a, T, V> {
+  node_stack: Vec<&'a Node<T, V>>,
+  start: Bound<T>,
+  end: Bound<T>,
+}
+
+impl<'a, T: Ord, V> Overlapping<'a, T, V> {
+  // 区间终点是否不早于查询的起点
+  fn reaches_start(&self, end: &T) -> bool {
+    match &self.start {
+      Bound::Included(start) => end >= start,
+      Bound::Excluded(start) => end > start,
+      Bound::Unbounded => true,
+    }
+  }
+
+  // 区间起点是否不晚于查询的终点
+  fn before_end(&self, start: &T) -> bool {
+    match &self.end {
+      Bound::Included(end) => start <= end,
+      Bound::Excluded(end) => start < end,
+      Bound::Unbounded => true,
+    }
+  }
+
+  // 沿左子树下降，最大右端点早于查询起点的子树整体跳过
+  fn push_left_path(&mut self, mut child: &'a Option<Box<Node<T, V>>>) {
+    while let Some(node) = child {
+      match &node.summary.0 {
+        Some(max_end) if self.reaches_start(max_end) => {}
+        _ => break,
+      }
+      self.node_stack.push(node.as_ref());
+      child = &node.left;
+    }
+  }
+}
+
+impl<'a, T: Ord, V> Iterator for Overlapping<'a, T, V> {
+  type Item = (&'a T, &'a T, &'a V);
+
+  fn next(&mut self) -> Option<Self::Item> {
+    while let Some(node) = self.node_stack.pop() {
+      let (start, end) = &node.key;
+      // 中序遍历中后续区间的起点只会更大，不可能再与查询相交
+      if !self.before_end(start) {
+        self.node_stack.clear();
+        return None;
+      }
+      self.push_left_path(&node.right);
+      if self.reaches_start(end) {
+        return Some((start, end, &node.value));
+      }
+    }
+    None
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{IntervalTree, InvalidInterval};
+  use std::ops::RangeInclusive;
+
+  #[test]
+  fn test_interval_tree() {
+    let mut tree = IntervalTree::new();
+    let mut intervals = Vec::new();
+    let mut seed = 7u32;
+    for i in 0..300 {
+      seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+      let start = (seed >> 16) % 1000;
+      let len = (seed >> 8) % 50;
+      if tree
+        .insert_interval(start..=start + len, i)
+        .unwrap()
+        .is_none()
+      {
+        intervals.push((start, start + len));
+      }
+    }
+    assert_eq!(tree.len(), intervals.len());
+    assert_eq!(
+      tree.insert_interval(RangeInclusive::new(5, 3), 0),
+      Err(InvalidInterval)
+    );
+    assert_eq!(tree.len(), intervals.len());
+    // 删除一部分区间，旋转后最大右端点仍需正确
+    for (start, end) in intervals.split_off(intervals.len() / 2) {
+      assert!(tree.remove_interval(&(start..=end)).is_some());
+    }
+    assert_eq!(tree.remove_interval(&(2000..=2001)), None);
+    assert_eq!(tree.len(), intervals.len());
+    intervals.sort();
+
+    for (a, b) in [
+      (0, 0),
+      (10, 20),
+      (500, 500),
+      (990, 1100),
+      (1049, 1049),
+      (2000, 3000),
+    ] {
+      let found: Vec<_> = tree.overlapping(a..=b).map(|(s, e, _)| (*s, *e)).collect();
+      let expected: Vec<_> = intervals
+        .iter()
+        .copied()
+        .filter(|&(s, e)| s <= b && e >= a)
+        .collect();
+      assert_eq!(found, expected);
+      let stabbed: Vec<_> = tree.stabbing(&a).map(|(s, e, _)| (*s, *e)).collect();
+      let expected: Vec<_> = intervals
+        .iter()
+        .copied()
+        .filter(|&(s, e)| s <= a && e >= a)
+        .collect();
+      assert_eq!(stabbed, expected);
+    }
+    assert_eq!(tree.overlapping(..).count(), intervals.len());
+    assert_eq!(
+      tree.overlapping(..10).count(),
+      intervals.iter().filter(|&&(s, _)| s < 10).count()
+    );
+  }
+}
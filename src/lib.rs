@@ -1,8 +1,17 @@
+pub mod avl_map;
+pub mod avl_multiset;
+pub mod avl_snapshot;
 pub mod avl_tree;
+pub mod b_plus_tree;
 pub mod b_tree;
+pub mod b_tree_map;
 pub mod binary_search_tree;
+pub mod codec;
+pub mod disk_b_tree;
 pub mod heap;
+pub mod interval_tree;
 pub mod linked_list;
+pub mod persistent_avl_tree;
 pub mod queue;
 pub mod stack;
 pub mod stack_using_linked_list;
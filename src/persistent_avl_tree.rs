@@ -0,0 +1,402 @@
+use std::{
+  borrow::Borrow,
+  cmp::{max, Ordering},
+  fmt::{self, Debug},
+  ops::Deref,
+  rc::Rc,
+  sync::Arc,
+};
+
+/// 节点使用的共享指针类型，Rc 用于单线程，Arc 可以跨线程共享快照
+pub trait PointerKind {
+  type Pointer<T>: Deref<Target = T> + Clone;
+
+  fn new<T>(value: T) -> Self::Pointer<T>;
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RcK;
+
+impl PointerKind for RcK {
+  type Pointer<T> = Rc<T>;
+
+  fn new<T>(value: T) -> Rc<T> {
+    Rc::new(value)
+  }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ArcK;
+
+impl PointerKind for ArcK {
+  type Pointer<T> = Arc<T>;
+
+  fn new<T>(value: T) -> Arc<T> {
+    Arc::new(value)
+  }
+}
+
+type Link<T, P> = Option<<P as PointerKind>::Pointer<Node<T, P>>>;
+
+// 节点创建后不再修改，修改操作只复制根到目标节点的路径，其余子树在版本之间共享
+struct Node<T, P: PointerKind> {
+  value: T,
+  left: Link<T, P>,
+  right: Link<T, P>,
+  height: usize,
+  size: usize,
+}
+
+fn height<T, P: PointerKind>(tree: &Link<T, P>) -> usize {
+  tree.as_ref().map_or(0, |n| n.height)
+}
+
+fn size<T, P: PointerKind>(tree: &Link<T, P>) -> usize {
+  tree.as_ref().map_or(0, |n| n.size)
+}
+
+// 由值与左右子树创建新节点，调用者保证左右子树高度差不超过 1
+fn make<T, P: PointerKind>(
+  value: T,
+  left: Link<T, P>,
+  right: Link<T, P>,
+) -> P::Pointer<Node<T, P>> {
+  P::new(Node {
+    height: 1 + max(height::<T, P>(&left), height::<T, P>(&right)),
+    size: 1 + size::<T, P>(&left) + size::<T, P>(&right),
+    value,
+    left,
+    right,
+  })
+}
+
+// 创建新节点，高度差为 2 时通过复制旋转路径上的节点恢复平衡
+fn balance<T: Clone, P: PointerKind>(
+  value: T,
+  left: Link<T, P>,
+  right: Link<T, P>,
+) -> P::Pointer<Node<T, P>> {
+  let (left_height, right_height) = (height::<T, P>(&left), height::<T, P>(&right));
+  if left_height > right_height + 1 {
+    let l = left.unwrap();
+    if height::<T, P>(&l.left) >= height::<T, P>(&l.right) {
+      // LL 型，右旋
+      let new_right = make::<T, P>(value, l.right.clone(), right);
+      make::<T, P>(l.value.clone(), l.left.clone(), Some(new_right))
+    } else {
+      // LR 型，先左旋再右旋
+      let lr = l.right.as_ref().unwrap();
+      let new_left = make::<T, P>(l.value.clone(), l.left.clone(), lr.left.clone());
+      let new_right = make::<T, P>(value, lr.right.clone(), right);
+      make::<T, P>(lr.value.clone(), Some(new_left), Some(new_right))
+    }
+  } else if right_height > left_height + 1 {
+    let r = right.unwrap();
+    if height::<T, P>(&r.right) >= height::<T, P>(&r.left) {
+      // RR 型，左旋
+      let new_left = make::<T, P>(value, left, r.left.clone());
+      make::<T, P>(r.value.clone(), Some(new_left), r.right.clone())
+    } else {
+      // RL 型，先右旋再左旋
+      let rl = r.left.as_ref().unwrap();
+      let new_left = make::<T, P>(value, left, rl.left.clone());
+      let new_right = make::<T, P>(r.value.clone(), rl.right.clone(), r.right.clone());
+      make::<T, P>(rl.value.clone(), Some(new_left), Some(new_right))
+    }
+  } else {
+    make::<T, P>(value, left, right)
+  }
+}
+
+// 插入值，值已存在时返回 None 表示树没有变化
+fn insert<T: Ord + Clone, P: PointerKind>(
+  tree: &Link<T, P>,
+  value: T,
+) -> Option<P::Pointer<Node<T, P>>> {
+  let Some(node) = tree else {
+    return Some(make::<T, P>(value, None, None));
+  };
+  match value.cmp(&node.value) {
+    Ordering::Equal => None,
+    Ordering::Less => {
+      let left = insert::<T, P>(&node.left, value)?;
+      Some(balance::<T, P>(
+        node.value.clone(),
+        Some(left),
+        node.right.clone(),
+      ))
+    }
+    Ordering::Greater => {
+      let right = insert::<T, P>(&node.right, value)?;
+      Some(balance::<T, P>(
+        node.value.clone(),
+        node.left.clone(),
+        Some(right),
+      ))
+    }
+  }
+}
+
+// 删除值，值不存在时返回 None 表示树没有变化
+fn remove<T, P, Q>(tree: &Link<T, P>, value: &Q) -> Option<Link<T, P>>
+where
+  T: Ord + Clone + Borrow<Q>,
+  P: PointerKind,
+  Q: Ord + ?Sized,
+{
+  let node = tree.as_ref()?;
+  match value.cmp(node.value.borrow()) {
+    Ordering::Less => {
+      let left = remove::<T, P, Q>(&node.left, value)?;
+      Some(Some(balance::<T, P>(
+        node.value.clone(),
+        left,
+        node.right.clone(),
+      )))
+    }
+    Ordering::Greater => {
+      let right = remove::<T, P, Q>(&node.right, value)?;
+      Some(Some(balance::<T, P>(
+        node.value.clone(),
+        node.left.clone(),
+        right,
+      )))
+    }
+    Ordering::Equal => Some(match (&node.left, &node.right) {
+      (None, child) | (child, None) => child.clone(),
+      // 用右子树的最小值替换被删除的节点
+      (left, Some(right)) => {
+        let (min, rest) = remove_min::<T, P>(right);
+        Some(balance::<T, P>(min, left.clone(), rest))
+      }
+    }),
+  }
+}
+
+// 删除子树中的最小值，返回该值与剩余的子树
+fn remove_min<T: Clone, P: PointerKind>(node: &Node<T, P>) -> (T, Link<T, P>) {
+  match &node.left {
+    None => (node.value.clone(), node.right.clone()),
+    Some(left) => {
+      let (min, left) = remove_min::<T, P>(left);
+      (
+        min,
+        Some(balance::<T, P>(
+          node.value.clone(),
+          left,
+          node.right.clone(),
+        )),
+      )
+    }
+  }
+}
+
+/// 持久化 AVL 树，insert 与 remove 不修改当前版本，而是在 O(log n) 内返回新版本，
+/// 新旧版本共享未修改的子树，clone 只复制根指针。使用 ArcK 时快照可以跨线程发送
+pub struct PersistentAVLTree<T, P: PointerKind = RcK> {
+  root: Link<T, P>,
+}
+
+impl<T, P: PointerKind> Clone for PersistentAVLTree<T, P> {
+  fn clone(&self) -> Self {
+    Self {
+      root: self.root.clone(),
+    }
+  }
+}
+
+impl<T, P: PointerKind> Default for PersistentAVLTree<T, P> {
+  fn default() -> Self {
+    Self { root: None }
+  }
+}
+
+impl<T: Debug, P: PointerKind> Debug for PersistentAVLTree<T, P> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_set().entries(self.iter()).finish()
+  }
+}
+
+impl<T: Ord + Clone> PersistentAVLTree<T> {
+  pub fn new() -> Self {
+    Self::default()
+  }
+}
+
+impl<T: Ord + Clone> PersistentAVLTree<T, ArcK> {
+  // 使用 Arc 节点，快照可以在线程之间共享
+  pub fn new_sync() -> Self {
+    Self::default()
+  }
+}
+
+impl<T: Ord + Clone, P: PointerKind> PersistentAVLTree<T, P> {
+  // 返回插入 value 后的新版本，value 已存在时返回当前版本的副本
+  pub fn insert(&self, value: T) -> Self {
+    match insert::<T, P>(&self.root, value) {
+      Some(root) => Self { root: Some(root) },
+      None => self.clone(),
+    }
+  }
+
+  // 返回删除 value 后的新版本，value 不存在时返回当前版本的副本
+  pub fn remove<Q>(&self, value: &Q) -> Self
+  where
+    T: Borrow<Q>,
+    Q: Ord + ?Sized,
+  {
+    match remove::<T, P, Q>(&self.root, value) {
+      Some(root) => Self { root },
+      None => self.clone(),
+    }
+  }
+
+  pub fn get<Q>(&self, value: &Q) -> Option<&T>
+  where
+    T: Borrow<Q>,
+    Q: Ord + ?Sized,
+  {
+    let mut child = &self.root;
+    while let Some(node) = child {
+      child = match value.cmp(node.value.borrow()) {
+        Ordering::Equal => return Some(&node.value),
+        Ordering::Less => &node.left,
+        Ordering::Greater => &node.right,
+      };
+    }
+    None
+  }
+
+  pub fn contains<Q>(&self, value: &Q) -> bool
+  where
+    T: Borrow<Q>,
+    Q: Ord + ?Sized,
+  {
+    self.get(value).is_some()
+  }
+}
+
+impl<T, P: PointerKind> PersistentAVLTree<T, P> {
+  pub fn len(&self) -> usize {
+    size::<T, P>(&self.root)
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.root.is_none()
+  }
+
+  pub fn iter(&self) -> Iter<'_, T, P> {
+    let mut iter = Iter {
+      node_stack: Vec::with_capacity(height::<T, P>(&self.root)),
+    };
+    iter.push_left_path(&self.root);
+    iter
+  }
+}
+
+impl<T: Ord + Clone, P: PointerKind> FromIterator<T> for PersistentAVLTree<T, P> {
+  fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+    iter
+      .into_iter()
+      .fold(Self::default(), |tree, value| tree.insert(value))
+  }
+}
+
+pub struct Iter<'a, T, P: PointerKind> {
+  node_stack: Vec<&'a Node<T, P>>,
+}
+
+impl<'a, T, P: PointerKind> Iter<'a, T, P> {
+  fn push_left_path(&mut self, mut child: &'a Link<T, P>) {
+    while let Some(node) = child {
+      self.node_stack.push(node);
+      child = &node.left;
+    }
+  }
+}
+
+impl<'a, T, P: PointerKind> Iterator for Iter<'a, T, P> {
+  type Item = &'a T;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    let node = self.node_stack.pop()?;
+    self.push_left_path(&node.right);
+    Some(&node.value)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{height, Link, PersistentAVLTree, PointerKind, RcK};
+  use std::{rc::Rc, thread};
+
+  // 检查每个节点的平衡因子、高度与子树大小
+  fn check<T, P: PointerKind>(tree: &Link<T, P>) -> usize {
+    let Some(node) = tree else {
+      return 0;
+    };
+    let (left, right) = (check::<T, P>(&node.left), check::<T, P>(&node.right));
+    assert!(left.abs_diff(right) <= 1);
+    assert_eq!(node.height, 1 + left.max(right));
+    assert_eq!(node.height, height::<T, P>(tree));
+    node.height
+  }
+
+  #[test]
+  fn test_persistent_avl_tree() {
+    let mut versions = vec![PersistentAVLTree::new()];
+    for i in 0..100 {
+      let next = versions.last().unwrap().insert((i * 37) % 100);
+      check::<i32, RcK>(&next.root);
+      versions.push(next);
+    }
+    // 旧版本保持不变
+    for (i, version) in versions.iter().enumerate() {
+      assert_eq!(version.len(), i);
+    }
+    assert_eq!(
+      versions[3].iter().copied().collect::<Vec<_>>(),
+      vec![0, 37, 74]
+    );
+    let full = versions.last().unwrap().clone();
+    assert_eq!(
+      full.iter().copied().collect::<Vec<_>>(),
+      (0..100).collect::<Vec<_>>()
+    );
+    assert_eq!(full.insert(50).len(), 100);
+
+    let mut removed = full.clone();
+    for i in (0..100).step_by(3) {
+      removed = removed.remove(&i);
+      check::<i32, RcK>(&removed.root);
+    }
+    assert_eq!(removed.len(), 66);
+    assert!(!removed.contains(&3) && removed.contains(&4));
+    assert_eq!(full.len(), 100);
+    assert!(full.contains(&3));
+
+    // 新版本只复制了插入路径，与旧版本共享其余子树
+    let root = full.root.as_ref().unwrap();
+    let next = full.insert(1000);
+    let next_root = next.root.as_ref().unwrap();
+    assert!(Rc::ptr_eq(
+      root.left.as_ref().unwrap(),
+      next_root.left.as_ref().unwrap()
+    ));
+  }
+
+  #[test]
+  fn test_persistent_avl_tree_sync() {
+    let base: PersistentAVLTree<i32, _> = PersistentAVLTree::new_sync().insert(1).insert(2);
+    let handles: Vec<_> = (0..4)
+      .map(|i| {
+        let snapshot = base.clone();
+        thread::spawn(move || snapshot.insert(10 + i).len())
+      })
+      .collect();
+    for handle in handles {
+      assert_eq!(handle.join().unwrap(), 3);
+    }
+    assert_eq!(base.len(), 2);
+  }
+}
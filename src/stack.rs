@@ -1,4 +1,4 @@
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct Stack<T> {
   data: Vec<T>, // 数据
 }
@@ -44,6 +44,6 @@ mod tests {
     s.pop();
     s.pop();
     s.pop();
-    assert_eq!(s.is_empty(), true);
+    assert!(s.is_empty());
   }
 }
@@ -6,11 +6,8 @@ pub struct UnionSet {
 
 impl UnionSet {
   pub fn new(n: usize) -> Self {
-    let mut parent = vec![0; n];
+    let parent = (0..n).collect();
     let size = vec![1; n];
-    for i in 0..n {
-      parent[i] = i;
-    }
     Self {
       parent,
       size,
@@ -64,8 +61,8 @@ mod tests {
     u.union(6, 7);
     u.union(7, 8);
     u.union(8, 9);
-    assert_eq!(u.is_connected(1, 4), true);
-    assert_eq!(u.is_connected(1, 5), false);
+    assert!(u.is_connected(1, 4));
+    assert!(!u.is_connected(1, 5));
     assert_eq!(u.count(), 2);
   }
 }